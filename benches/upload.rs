@@ -0,0 +1,89 @@
+//! Upload throughput for a fully dirty 4K canvas.
+//!
+//! `upload_bytes` compares the two ways of turning packed pixels into RGBA8 upload rows:
+//! unpacking every pixel, and casting whole rows (what the plugin does on little-endian targets).
+//! `prepare_uploads` times building the upload ops for every chunk through the plugin itself.
+
+use bevy::{prelude::*, render::RenderApp};
+use bevy_canvas_2d::prelude::*;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+const CANVAS_SIZE: UVec2 = UVec2::new(3840, 2160);
+const CHUNK_SIZE: UVec2 = UVec2::splat(256);
+
+/// Canvas pixels, row-major with stride = canvas width.
+fn canvas_pixels() -> Vec<u32> {
+    (0..CANVAS_SIZE.element_product())
+        .map(|i| pack_rgba8([i as u8, (i >> 8) as u8, (i >> 16) as u8, 255]))
+        .collect()
+}
+
+/// Append every chunk's rows to `bytes`, one upload at a time, using `extend_row`.
+fn build_uploads(bytes: &mut Vec<u8>, pixels: &[u32], extend_row: impl Fn(&mut Vec<u8>, &[u32])) {
+    let chunks = (CANVAS_SIZE + CHUNK_SIZE - UVec2::ONE) / CHUNK_SIZE;
+    for chunk_y in 0..chunks.y {
+        for chunk_x in 0..chunks.x {
+            let min = UVec2::new(chunk_x, chunk_y) * CHUNK_SIZE;
+            let extent = CHUNK_SIZE.min(CANVAS_SIZE - min);
+
+            bytes.clear();
+            for y in min.y..min.y + extent.y {
+                let start = (y * CANVAS_SIZE.x + min.x) as usize;
+                extend_row(bytes, &pixels[start..start + extent.x as usize]);
+            }
+            std::hint::black_box(&bytes);
+        }
+    }
+}
+
+fn upload_bytes(c: &mut Criterion) {
+    let pixels = canvas_pixels();
+    let mut bytes = Vec::with_capacity((CHUNK_SIZE.element_product() * 4) as usize);
+
+    let mut group = c.benchmark_group("upload_bytes");
+    group.throughput(Throughput::Bytes(CANVAS_SIZE.element_product() as u64 * 4));
+    group.bench_function("unpack", |b| {
+        b.iter(|| {
+            build_uploads(&mut bytes, &pixels, |bytes, row| {
+                for &px in row {
+                    bytes.extend_from_slice(&unpack_rgba8(px));
+                }
+            })
+        })
+    });
+    group.bench_function("cast", |b| {
+        b.iter(|| {
+            build_uploads(&mut bytes, &pixels, |bytes, row| {
+                bytes.extend_from_slice(bytemuck::cast_slice(row))
+            })
+        })
+    });
+    group.finish();
+}
+
+fn prepare_uploads(c: &mut Criterion) {
+    // Headless: an empty render sub-app stands in for the renderer, so ops are built and recycled each update
+    let mut app = App::new();
+    app.insert_sub_app(RenderApp, SubApp::new());
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(Assets::<Image>::default())
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Size(CHUNK_SIZE),
+                ..default()
+            },
+        })
+        .add_systems(Update, |mut painter: CanvasPainter| {
+            painter.par_for_each_chunk(|_, _, dirty| dirty.mark_all());
+        });
+    app.update();
+
+    let mut group = c.benchmark_group("prepare_uploads");
+    group.throughput(Throughput::Bytes(CANVAS_SIZE.element_product() as u64 * 4));
+    group.bench_function("full_4k", |b| b.iter(|| app.update()));
+    group.finish();
+}
+
+criterion_group!(benches, upload_bytes, prepare_uploads);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::prelude::*;
 use bevy_canvas_2d::prelude::*;
 
 mod shared;
@@ -12,7 +12,7 @@ fn main() {
         .add_plugins(CanvasPlugin {
             config: CanvasConfig {
                 canvas_size: CANVAS_SIZE,
-                num_chunks: U8Vec2::splat(4),
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
                 ..default()
             },
         })
@@ -27,5 +27,8 @@ fn clear_colour(mut clear_canvas_msg: MessageWriter<ClearCanvas>, mut seeded_rng
     let [r, g, b, a] = shared::random_colour(rng);
     let colour = pack_rgba8([r, g, b, a]);
 
-    clear_canvas_msg.write(ClearCanvas { rgba_u32: colour });
+    clear_canvas_msg.write(ClearCanvas {
+        rgba_u32: colour,
+        ..default()
+    });
 }
@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::new(512, 256);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::new(4, 2)),
+                storage: CanvasStorage::Contiguous,
+                ..default()
+            },
+        })
+        .add_systems(Update, diffuse)
+        .run();
+}
+
+/// Drop random colour seeds, then blur every pixel with its four neighbours across chunk edges.
+fn diffuse(mut painter: CanvasPainter, mut seeded_rng: ResMut<shared::SeededRng>, mut scratch: Local<Vec<u32>>) {
+    let rng = seeded_rng.rng();
+
+    for _ in 0..8 {
+        let pos = UVec2::new(rng.random_range(0..CANVAS_SIZE.x), rng.random_range(0..CANVAS_SIZE.y));
+        let [r, g, b, _] = shared::random_colour(rng);
+        painter.set_pixel(pos.as_ivec2(), pack_rgba8([r, g, b, 255]));
+    }
+
+    let Some((pixels, stride)) = painter.contiguous_mut() else {
+        return;
+    };
+
+    let (w, h) = (CANVAS_SIZE.x as usize, CANVAS_SIZE.y as usize);
+    scratch.clear();
+    scratch.extend_from_slice(pixels);
+
+    for y in 0..h {
+        let up = (y + 1) % h;
+        let down = (y + h - 1) % h;
+        for x in 0..w {
+            let left = (x + w - 1) % w;
+            let right = (x + 1) % w;
+
+            let mut sum = [0u32; 3];
+            for index in [
+                y * stride + x,
+                y * stride + left,
+                y * stride + right,
+                up * stride + x,
+                down * stride + x,
+            ] {
+                let [r, g, b, _] = unpack_rgba8(scratch[index]);
+                sum[0] += r as u32;
+                sum[1] += g as u32;
+                sum[2] += b as u32;
+            }
+            pixels[y * stride + x] = pack_rgba8([(sum[0] / 5) as u8, (sum[1] / 5) as u8, (sum[2] / 5) as u8, 255]);
+        }
+    }
+
+    painter.mark_dirty(UVec2::ZERO, CANVAS_SIZE);
+}
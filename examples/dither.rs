@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(512);
+
+fn main() {
+    // Four-shade retro palette
+    let palette = vec![
+        pack_rgba8([15, 56, 15, 255]),
+        pack_rgba8([48, 98, 48, 255]),
+        pack_rgba8([139, 172, 15, 255]),
+        pack_rgba8([155, 188, 15, 255]),
+    ];
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(shared::CameraPlugin)
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
+                quantiser: Some(CanvasQuantiser::new(palette, Dither::Bayer8)),
+                ..default()
+            },
+        })
+        .add_systems(Startup, draw_gradient)
+        .run();
+}
+
+/// Draw a smooth gradient; the quantiser dithers it down to the palette on write.
+fn draw_gradient(mut draw_rect_msg: MessageWriter<DrawRect>) {
+    let mut pixels = Vec::with_capacity((CANVAS_SIZE.x * CANVAS_SIZE.y) as usize);
+    for y in 0..CANVAS_SIZE.y {
+        for x in 0..CANVAS_SIZE.x {
+            let r = (x * 255 / CANVAS_SIZE.x) as u8;
+            let g = (y * 255 / CANVAS_SIZE.y) as u8;
+            pixels.push(pack_rgba8([r, g, 32, 255]));
+        }
+    }
+
+    draw_rect_msg.write(DrawRect {
+        start: IVec2::ZERO,
+        size: CANVAS_SIZE,
+        rgba_u32: pixels,
+        ..default()
+    });
+}
@@ -1,4 +1,4 @@
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::prelude::*;
 use bevy_canvas_2d::prelude::*;
 use rand::Rng;
 
@@ -13,7 +13,7 @@ fn main() {
         .add_plugins(CanvasPlugin {
             config: CanvasConfig {
                 canvas_size: CANVAS_SIZE,
-                num_chunks: U8Vec2::splat(4),
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
                 ..default()
             },
         })
@@ -31,7 +31,8 @@ fn draw_pixel(mut draw_pixel_msg: MessageWriter<DrawPixel>, mut seeded_rng: ResM
     let colour = pack_rgba8([r, g, b, a]);
 
     draw_pixel_msg.write(DrawPixel {
-        pos: UVec2::new(x, y),
+        pos: UVec2::new(x, y).as_ivec2(),
         rgba_u32: colour,
+        ..default()
     });
 }
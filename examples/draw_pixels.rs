@@ -1,4 +1,4 @@
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::prelude::*;
 use bevy_canvas_2d::prelude::*;
 use rand::Rng;
 
@@ -13,7 +13,7 @@ fn main() {
         .add_plugins(CanvasPlugin {
             config: CanvasConfig {
                 canvas_size: CANVAS_SIZE,
-                num_chunks: U8Vec2::splat(1),
+                chunking: CanvasChunking::Count(UVec2::splat(1)),
                 ..default()
             },
         })
@@ -35,9 +35,13 @@ fn draw_pixels(mut draw_pixels_msg: MessageWriter<DrawPixels>, mut seeded_rng: R
         let [r, g, b, a] = shared::random_colour(rng);
         let colour = pack_rgba8([r, g, b, a]);
 
-        positions.push(UVec2::new(x, y));
+        positions.push(UVec2::new(x, y).as_ivec2());
         rgba_u32.push(colour);
     }
 
-    draw_pixels_msg.write(DrawPixels { positions, rgba_u32 });
+    draw_pixels_msg.write(DrawPixels {
+        positions,
+        rgba_u32,
+        ..default()
+    });
 }
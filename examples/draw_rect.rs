@@ -1,4 +1,4 @@
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::prelude::*;
 use bevy_canvas_2d::prelude::*;
 use rand::Rng;
 
@@ -13,7 +13,7 @@ fn main() {
         .add_plugins(CanvasPlugin {
             config: CanvasConfig {
                 canvas_size: CANVAS_SIZE,
-                num_chunks: U8Vec2::splat(4),
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
                 ..default()
             },
         })
@@ -34,8 +34,9 @@ fn draw_rect(mut draw_rect_msg: MessageWriter<DrawRect>, mut seeded_rng: ResMut<
     let colour = pack_rgba8([r, g, b, a]);
 
     draw_rect_msg.write(DrawRect {
-        start: UVec2::new(x, y),
+        start: UVec2::new(x, y).as_ivec2(),
         size: UVec2::new(w, h),
         rgba_u32: vec![colour; (w * h) as usize],
+        ..default()
     });
 }
@@ -1,4 +1,4 @@
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::prelude::*;
 use bevy_canvas_2d::prelude::*;
 use rand::Rng;
 
@@ -13,7 +13,7 @@ fn main() {
         .add_plugins(CanvasPlugin {
             config: CanvasConfig {
                 canvas_size: CANVAS_SIZE,
-                num_chunks: U8Vec2::splat(4),
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
                 ..default()
             },
         })
@@ -32,6 +32,7 @@ fn draw_span(mut draw_span_msg: MessageWriter<DrawSpan>, mut seeded_rng: ResMut<
     draw_span_msg.write(DrawSpan {
         start: counter_to_pos(*counter),
         rgba_u32: vec![colour; l],
+        ..default()
     });
 
     *counter += l;
@@ -40,9 +41,9 @@ fn draw_span(mut draw_span_msg: MessageWriter<DrawSpan>, mut seeded_rng: ResMut<
 
 // -- Helpers --
 
-pub fn counter_to_pos(count: usize) -> UVec2 {
+pub fn counter_to_pos(count: usize) -> IVec2 {
     let x = (count as u32) % CANVAS_SIZE.x;
     let y = (count as u32) / CANVAS_SIZE.x;
 
-    UVec2::new(x, y)
+    UVec2::new(x, y).as_ivec2()
 }
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(512);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
+                ..default()
+            },
+        })
+        .add_plugins(SimGridPlugin::<bool>::default())
+        .add_systems(Startup, seed)
+        .add_systems(Update, step)
+        .run();
+}
+
+/// Create the grid with a random initial population.
+fn seed(mut commands: Commands, mut seeded_rng: ResMut<shared::SeededRng>) {
+    let rng = seeded_rng.rng();
+
+    let mut grid = SimGrid::new(CANVAS_SIZE, false, |&alive| {
+        if alive {
+            pack_rgba8([255, 255, 255, 255])
+        } else {
+            pack_rgba8([0, 0, 0, 255])
+        }
+    });
+    for cell in grid.front_mut() {
+        *cell = rng.random_bool(0.25);
+    }
+
+    commands.insert_resource(grid);
+}
+
+/// Advance one generation; only cells that changed are recoloured and uploaded.
+fn step(mut grid: ResMut<SimGrid<bool>>) {
+    grid.par_step(|pos, cells| {
+        let neighbours = cells.moore(pos).into_iter().filter(|&&alive| alive).count();
+        matches!((*cells.get(pos), neighbours), (true, 2) | (_, 3))
+    });
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(384);
+const MINIMAP_SIZE: UVec2 = UVec2::splat(128);
+
+/// Marks the second canvas.
+#[derive(Component)]
+struct Minimap;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Size(UVec2::splat(128)),
+                ..default()
+            },
+        })
+        .add_systems(Startup, spawn_minimap)
+        .add_systems(Update, (draw_rect, paint_minimap))
+        .run();
+}
+
+/// Spawn a second canvas to the right of the primary one.
+fn spawn_minimap(mut commands: Commands) {
+    commands.spawn((
+        Canvas::new(CanvasConfig {
+            canvas_size: MINIMAP_SIZE,
+            clear_colour: pack_rgba8([0, 0, 0, 255]),
+            ..default()
+        }),
+        Minimap,
+        Transform::from_xyz((CANVAS_SIZE.x + MINIMAP_SIZE.x) as f32 / 2.0 + 16.0, 0.0, 0.0),
+    ));
+}
+
+/// Draw a random rectangle to the primary canvas with a message.
+fn draw_rect(mut draw_rect_msg: MessageWriter<DrawRect>, mut seeded_rng: ResMut<shared::SeededRng>) {
+    let rng = seeded_rng.rng();
+
+    let start = UVec2::new(rng.random_range(0..CANVAS_SIZE.x), rng.random_range(0..CANVAS_SIZE.y));
+    let size = UVec2::new(rng.random_range(1..=32), rng.random_range(1..=32));
+    let [r, g, b, a] = shared::random_colour(rng);
+    draw_rect_msg.write(DrawRect {
+        start: start.as_ivec2(),
+        size,
+        rgba_u32: vec![pack_rgba8([r, g, b, a]); (size.x * size.y) as usize],
+        ..default()
+    });
+}
+
+/// Mirror a downscaled copy of the primary canvas into the minimap.
+fn paint_minimap(canvas: CanvasReader<Without<Minimap>>, mut minimap: CanvasPainter<With<Minimap>>) {
+    let scale = CANVAS_SIZE / MINIMAP_SIZE;
+    minimap.map_region(UVec2::ZERO, MINIMAP_SIZE, |pos, _| canvas.get_pixel(pos * scale));
+}
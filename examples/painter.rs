@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(512);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
+                ..default()
+            },
+        })
+        .add_systems(Update, paint)
+        .run();
+}
+
+/// Paint directly into the canvas without allocating draw messages.
+fn paint(mut painter: CanvasPainter, mut seeded_rng: ResMut<shared::SeededRng>) {
+    let rng = seeded_rng.rng();
+
+    for _ in 0..64 {
+        let pos = UVec2::new(rng.random_range(0..CANVAS_SIZE.x), rng.random_range(0..CANVAS_SIZE.y));
+        let [r, g, b, a] = shared::random_colour(rng);
+        painter.set_pixel(pos.as_ivec2(), pack_rgba8([r, g, b, a]));
+    }
+
+    let start = UVec2::new(rng.random_range(0..CANVAS_SIZE.x), rng.random_range(0..CANVAS_SIZE.y));
+    let size = UVec2::new(rng.random_range(1..=32), rng.random_range(1..=32));
+    let [r, g, b, a] = shared::random_colour(rng);
+    painter.fill_rect(start.as_ivec2(), size, pack_rgba8([r, g, b, a]));
+}
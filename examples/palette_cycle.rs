@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(512);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(shared::CameraPlugin)
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(4)),
+                pixel_format: CanvasPixelFormat::Indexed8,
+                ..default()
+            },
+        })
+        .add_systems(Startup, draw_rings)
+        .add_systems(Update, cycle_palette)
+        .run();
+}
+
+/// Draw concentric rings of palette indices once.
+fn draw_rings(mut draw_rect_msg: MessageWriter<DrawRect>) {
+    let centre = CANVAS_SIZE.as_vec2() / 2.0;
+
+    let mut indices = Vec::with_capacity((CANVAS_SIZE.x * CANVAS_SIZE.y) as usize);
+    for y in 0..CANVAS_SIZE.y {
+        for x in 0..CANVAS_SIZE.x {
+            let distance = Vec2::new(x as f32, y as f32).distance(centre);
+            indices.push((distance as u32) % 256);
+        }
+    }
+
+    draw_rect_msg.write(DrawRect {
+        start: IVec2::ZERO,
+        size: CANVAS_SIZE,
+        rgba_u32: indices,
+        ..default()
+    });
+}
+
+/// Rotate a rainbow through the palette; only the palette changes each frame.
+fn cycle_palette(time: Res<Time>, mut palette: ResMut<CanvasPalette>) {
+    let offset = time.elapsed_secs() * 90.0;
+
+    for index in 0..=255u8 {
+        let hue = (index as f32 * 360.0 / 256.0 + offset) % 360.0;
+        let [r, g, b, a] = Color::hsl(hue, 0.8, 0.5).to_srgba().to_u8_array();
+        palette.set(index, pack_rgba8([r, g, b, a]));
+    }
+}
@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(1024);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(shared::CameraPlugin)
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(8)),
+                ..default()
+            },
+        })
+        .add_systems(Update, plasma)
+        .run();
+}
+
+/// Redraw every chunk in parallel with an animated plasma pattern.
+fn plasma(time: Res<Time>, mut painter: CanvasPainter) {
+    let t = time.elapsed_secs();
+
+    painter.par_for_each_chunk(|_chunk_key, pixels, dirty| {
+        let chunk_size = dirty.chunk_size();
+        let origin = dirty.origin();
+
+        for (i, px) in pixels.iter_mut().enumerate() {
+            let x = (origin.x + i as u32 % chunk_size.x) as f32 * 0.02;
+            let y = (origin.y + i as u32 / chunk_size.x) as f32 * 0.02;
+            let v = (x + t).sin() + (y - t).cos() + ((x + y) * 0.5 + t).sin();
+
+            let r = ((v * 1.0).sin() * 127.0 + 128.0) as u8;
+            let g = ((v * 2.0).sin() * 127.0 + 128.0) as u8;
+            let b = ((v * 3.0).sin() * 127.0 + 128.0) as u8;
+            *px = pack_rgba8([r, g, b, 255]);
+        }
+
+        dirty.mark_all();
+    });
+}
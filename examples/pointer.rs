@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::new(256, 192);
+const BRUSH_SIZE: UVec2 = UVec2::splat(3);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(shared::CameraPlugin)
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                origin: CanvasOrigin::TopLeft,
+                anchor: CanvasAnchor::TopLeft,
+                pixel_scale: Vec2::new(3.0, 2.0),
+                ..default()
+            },
+        })
+        .add_systems(Startup, place_canvas)
+        .add_systems(Update, paint_at_cursor)
+        .run();
+}
+
+/// Place the canvas's top-left corner so the canvas is centred on screen.
+fn place_canvas(canvas: Single<(&Canvas, &mut Transform), With<PrimaryCanvas>>) {
+    let (canvas, mut transform) = canvas.into_inner();
+    let local_size = canvas.config().local_size();
+    transform.translation = Vec3::new(-local_size.x / 2.0, local_size.y / 2.0, 0.0);
+}
+
+/// Paint under the cursor while the left mouse button is held.
+fn paint_at_cursor(
+    buttons: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    canvas: Single<(Entity, &Canvas, &GlobalTransform), With<PrimaryCanvas>>,
+    mut draw_rect_msg: MessageWriter<DrawRect>,
+) {
+    if !buttons.pressed(MouseButton::Left) {
+        return;
+    }
+
+    let (camera, camera_transform) = *camera;
+    let Some(world) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    // Canvas coordinates are top-left origin, so the brush extends right and down from the cursor
+    let (entity, canvas, canvas_transform) = *canvas;
+    if let Some(start) = canvas.world_to_pixel(canvas_transform, world.extend(0.0)) {
+        draw_rect_msg.write(DrawRect {
+            canvas: Some(entity),
+            start: start.as_ivec2(),
+            size: BRUSH_SIZE,
+            rgba_u32: vec![pack_rgba8([0, 0, 0, 255]); BRUSH_SIZE.element_product() as usize],
+            ..default()
+        });
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+/// Sizes the canvas cycles through.
+const SIZES: [UVec2; 3] = [UVec2::splat(512), UVec2::new(768, 512), UVec2::new(384, 256)];
+
+/// Chunk size kept across resizes.
+const CHUNK_SIZE: UVec2 = UVec2::splat(128);
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: SIZES[0],
+                chunking: CanvasChunking::Size(CHUNK_SIZE),
+                ..default()
+            },
+        })
+        .add_systems(Update, (draw_rect, resize))
+        .run();
+}
+
+/// Draw a random rectangle each frame, within the current canvas size.
+fn draw_rect(mut painter: CanvasPainter, mut seeded_rng: ResMut<shared::SeededRng>) {
+    let rng = seeded_rng.rng();
+    let canvas_size = painter.canvas_size();
+
+    let start = UVec2::new(rng.random_range(0..canvas_size.x), rng.random_range(0..canvas_size.y));
+    let size = UVec2::new(rng.random_range(1..=32), rng.random_range(1..=32));
+    let [r, g, b, a] = shared::random_colour(rng);
+    painter.fill_rect(start.as_ivec2(), size, pack_rgba8([r, g, b, a]));
+}
+
+/// Every two seconds, resize to the next size, keeping the content centred.
+fn resize(time: Res<Time>, mut timer: Local<f32>, mut index: Local<usize>, mut resize_msg: MessageWriter<ResizeCanvas>) {
+    *timer += time.delta_secs();
+    if *timer < 2.0 {
+        return;
+    }
+    *timer = 0.0;
+
+    *index = (*index + 1) % SIZES.len();
+    resize_msg.write(ResizeCanvas {
+        size: SIZES[*index],
+        chunking: CanvasChunking::Size(CHUNK_SIZE),
+        anchor: CanvasAnchor::Centre,
+        ..default()
+    });
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::new(320, 240);
+const BACKGROUND: [u8; 4] = [16, 16, 24, 255];
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(shared::CameraPlugin)
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(2)),
+                clear_colour: pack_rgba8(BACKGROUND),
+                pixel_scale: Vec2::splat(2.0),
+                // Shapes leaving the canvas are cut off rather than reappearing on the opposite edge
+                wrap: CanvasWrap::splat(WrapMode::Clip),
+                ..default()
+            },
+        })
+        .add_systems(Update, draw_shapes)
+        .run();
+}
+
+/// Clear, then draw shapes orbiting past the canvas edges at sub-pixel positions.
+fn draw_shapes(
+    time: Res<Time>,
+    mut clear_canvas_msg: MessageWriter<ClearCanvas>,
+    mut draw_shape_msg: MessageWriter<DrawShape>,
+) {
+    clear_canvas_msg.write(ClearCanvas {
+        rgba_u32: pack_rgba8(BACKGROUND),
+        ..default()
+    });
+
+    let t = time.elapsed_secs();
+    let centre = CANVAS_SIZE.as_vec2() / 2.0;
+
+    for i in 0..8 {
+        let angle = t * 0.3 + i as f32 * core::f32::consts::TAU / 8.0;
+        let pos = centre + Vec2::from_angle(angle) * Vec2::new(180.0, 130.0);
+        let colour = pack_rgba8([255 - i * 24, 80 + i * 20, 200, 255]);
+
+        let shape = if i % 2 == 0 {
+            CanvasShape::Circle {
+                centre: pos,
+                radius: 12.5 + 4.0 * (t + i as f32).sin(),
+            }
+        } else {
+            CanvasShape::Rect {
+                min: pos - Vec2::splat(10.25),
+                max: pos + Vec2::splat(10.25),
+            }
+        };
+
+        draw_shape_msg.write(DrawShape {
+            shape,
+            rgba_u32: colour,
+            ..default()
+        });
+    }
+}
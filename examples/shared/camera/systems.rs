@@ -34,7 +34,7 @@ pub fn zoom_camera(time: Res<Time>, keys: Res<ButtonInput<KeyCode>>, mut camera:
         };
 
         let factor = 1.0 + (zoom * ZOOM_SPEED * multiplier * time.delta_secs());
-        ortho.scale = ortho.scale * factor;
+        ortho.scale *= factor;
     }
 }
 
@@ -1,5 +1,8 @@
 mod camera;
+// Not every example draws random pixels
+#[allow(dead_code)]
 mod random;
 
 pub use camera::CameraPlugin;
+#[allow(unused_imports)]
 pub use random::{RandomPlugin, SeededRng, random_colour};
@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use bevy_canvas_2d::prelude::*;
+use rand::Rng;
+
+mod shared;
+
+const CANVAS_SIZE: UVec2 = UVec2::splat(256);
+const MOON_SIZE: UVec2 = UVec2::splat(64);
+
+/// Marks the canvas that orbits the primary canvas.
+#[derive(Component)]
+struct Moon;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((shared::CameraPlugin, shared::RandomPlugin))
+        .add_plugins(CanvasPlugin {
+            config: CanvasConfig {
+                canvas_size: CANVAS_SIZE,
+                chunking: CanvasChunking::Count(UVec2::splat(2)),
+                ..default()
+            },
+        })
+        .add_systems(Startup, spawn_moon)
+        .add_systems(Update, (paint, spin))
+        .run();
+}
+
+/// Spawn a small canvas as a child of the primary canvas, offset so it orbits as the parent rotates.
+fn spawn_moon(mut commands: Commands, canvas: Single<Entity, With<PrimaryCanvas>>) {
+    commands.spawn((
+        Canvas::new(CanvasConfig {
+            canvas_size: MOON_SIZE,
+            clear_colour: pack_rgba8([40, 40, 60, 255]),
+            ..default()
+        }),
+        Moon,
+        Transform::from_xyz(CANVAS_SIZE.x as f32, 0.0, 0.0),
+        ChildOf(*canvas),
+    ));
+}
+
+/// Paint random pixels into both canvases.
+fn paint(
+    mut canvas: CanvasPainter<(With<PrimaryCanvas>, Without<Moon>)>,
+    mut moon: CanvasPainter<With<Moon>>,
+    mut seeded_rng: ResMut<shared::SeededRng>,
+) {
+    let rng = seeded_rng.rng();
+
+    for _ in 0..32 {
+        let pos = UVec2::new(rng.random_range(0..CANVAS_SIZE.x), rng.random_range(0..CANVAS_SIZE.y));
+        let [r, g, b, a] = shared::random_colour(rng);
+        canvas.set_pixel(pos.as_ivec2(), pack_rgba8([r, g, b, a]));
+
+        let pos = UVec2::new(rng.random_range(0..MOON_SIZE.x), rng.random_range(0..MOON_SIZE.y));
+        moon.set_pixel(pos.as_ivec2(), pack_rgba8([255, 255, 255, 255]));
+    }
+}
+
+/// Rotate and pulse the primary canvas; the moon follows as its child.
+fn spin(time: Res<Time>, mut transform: Single<&mut Transform, With<PrimaryCanvas>>) {
+    let t = time.elapsed_secs();
+    transform.rotation = Quat::from_rotation_z(t * 0.5);
+    transform.scale = Vec3::splat(1.0 + 0.25 * (t * 2.0).sin());
+}
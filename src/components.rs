@@ -2,6 +2,100 @@
 
 use bevy::prelude::*;
 
+use super::{
+    config::{CanvasConfig, CanvasConfigError},
+    resources::{CanvasCpuChunks, CanvasDirtyRects, CanvasImageHandles},
+    writer::CanvasWriter,
+};
+
+/// A canvas: a grid of chunk images backed by CPU pixel buffers with per-chunk dirty tracking.
+///
+/// Spawn one per canvas; its chunk images are created in `PostUpdate` and their sprites spawned as children.
+/// Draw messages target a canvas by entity, and `CanvasPainter`/`CanvasReader` by query filter.
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct Canvas {
+    pub(crate) config: CanvasConfig,
+    pub(crate) cpu: CanvasCpuChunks,
+    pub(crate) dirty: CanvasDirtyRects,
+    pub(crate) images: CanvasImageHandles,
+}
+
+impl Canvas {
+    /// Create a canvas, with every pixel set to the clear colour.
+    ///
+    /// # Panics
+    /// If the configuration is invalid (see `CanvasConfig::validate`).
+    pub fn new(config: CanvasConfig) -> Self {
+        Self::try_new(config).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Create a canvas, returning an error if the configuration is invalid.
+    pub fn try_new(config: CanvasConfig) -> Result<Self, CanvasConfigError> {
+        config.validate()?;
+
+        let cpu = CanvasCpuChunks::new(
+            config.layout(),
+            config.pixel_format(),
+            config.storage(),
+            config.clear_colour(),
+        );
+        let dirty = CanvasDirtyRects::new(config.num_chunks(), config.chunk_size());
+
+        Ok(Self {
+            config,
+            cpu,
+            dirty,
+            images: CanvasImageHandles::default(),
+        })
+    }
+
+    /// Canvas configuration.
+    #[inline]
+    pub fn config(&self) -> &CanvasConfig {
+        &self.config
+    }
+
+    /// Total canvas size in pixels.
+    #[inline]
+    pub fn canvas_size(&self) -> UVec2 {
+        self.config.canvas_size()
+    }
+
+    /// Pixel under a world-space point, given the canvas entity's `GlobalTransform`, or `None` if it is outside the canvas.
+    ///
+    /// The point is projected onto the canvas plane along the canvas's local Z axis.
+    #[inline]
+    pub fn world_to_pixel(&self, transform: &GlobalTransform, world: Vec3) -> Option<UVec2> {
+        let local = transform.affine().inverse().transform_point3(world);
+        self.config.local_to_pixel_pos(local.truncate())
+    }
+
+    /// World-space position of a canvas position in pixels, given the canvas entity's `GlobalTransform`.
+    #[inline]
+    pub fn pixel_to_world(&self, transform: &GlobalTransform, pos: Vec2) -> Vec3 {
+        transform.transform_point(self.config.pixel_to_local(pos).extend(0.0))
+    }
+
+    /// Writer over the CPU buffers and dirty rects.
+    #[inline]
+    pub(crate) fn writer(&mut self) -> CanvasWriter<'_> {
+        CanvasWriter::new(
+            &mut self.cpu,
+            &mut self.dirty,
+            self.config.layout(),
+            self.config.wrap(),
+            self.config.write_quantiser(),
+        )
+    }
+}
+
+/// Marks the canvas spawned by `CanvasPlugin`.
+///
+/// Draw messages without a target canvas, the default `CanvasPainter`/`CanvasReader` and `SimGrid` all use it.
+#[derive(Component, Default)]
+pub struct PrimaryCanvas;
+
 /// Marker component for entities that render a canvas chunk image.
 #[derive(Component)]
 pub struct CanvasImage;
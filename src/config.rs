@@ -1,50 +1,378 @@
-//! Canvas configuration resource.
+//! Canvas configuration.
 //!
 //! The canvas is split into a grid of chunks.
-//! Each chunk is backed by a CPU `Vec<u32>` (packed RGBA8) and a GPU `Image`.
+//! Each chunk is backed by a CPU pixel buffer (packed RGBA8 `u32`s or palette `u8` indices) and a GPU `Image`.
 //! The GPU images are updated with partial texture uploads using dirty rectangles.
 
-use bevy::{math::U8Vec2, prelude::*};
+use bevy::{math::I64Vec2, prelude::*, render::render_resource::TextureFormat};
 
-use super::settings::{DEFAULT_CANVAS_SIZE, DEFAULT_CANVAS_Z_INDEX, DEFAULT_CLEAR_COLOUR, DEFAULT_NUM_CHUNKS};
+use super::{
+    quantise::CanvasQuantiser,
+    settings::{
+        DEFAULT_CANVAS_ANCHOR, DEFAULT_CANVAS_SIZE, DEFAULT_CANVAS_Z_INDEX, DEFAULT_CHUNK_SIZE, DEFAULT_CLEAR_COLOUR,
+        DEFAULT_MAX_TEXTURE_DIMENSION, DEFAULT_PIXEL_SCALE, MAX_TOTAL_CHUNKS,
+    },
+    types::CanvasLayout,
+    utils::{pack_rgba8, unpack_rgba8},
+};
 
-/// Runtime configuration for the canvas plugin.
-#[derive(Resource, Clone)]
+/// Configuration for a canvas.
+#[derive(Clone)]
 pub struct CanvasConfig {
     /// Clear colour used to initialise the GPU images and CPU buffers.
     pub clear_colour: u32,
 
-    /// Z-index used for the chunk sprites.
+    /// Z offset of the chunk sprites relative to the canvas entity.
     pub canvas_z_index: f32,
 
     /// Total canvas pixel resolution.
     pub canvas_size: UVec2,
 
-    /// Number of chunks in (x, y). Must evenly divide `canvas_size`.
-    pub num_chunks: U8Vec2,
+    /// How the canvas is split into chunks.
+    pub chunking: CanvasChunking,
+
+    /// Corner of the canvas that pixel (0, 0) is drawn at.
+    pub origin: CanvasOrigin,
+
+    /// Point of the canvas placed at the canvas entity's origin (default `CanvasAnchor::Centre`).
+    pub anchor: CanvasAnchor,
+
+    /// Size of one pixel in the canvas entity's local units; unequal axes give non-square pixels.
+    pub pixel_scale: Vec2,
+
+    /// How draw operations resolve pixels outside the canvas, per axis.
+    pub wrap: CanvasWrap,
+
+    /// How pixels are stored on the CPU.
+    pub pixel_format: CanvasPixelFormat,
+
+    /// How the CPU pixel buffers are laid out.
+    pub storage: CanvasStorage,
+
+    /// GPU texture format (and colour space) of the chunk images.
+    pub texture_format: CanvasTextureFormat,
+
+    /// Optional write-stage quantiser applied to every pixel written to an RGBA8 canvas.
+    pub quantiser: Option<CanvasQuantiser>,
+}
+
+/// How the canvas is split into chunks.
+///
+/// When the chunks do not divide the canvas exactly, the last column/row of chunks is smaller (ragged).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CanvasChunking {
+    /// Number of chunks in (x, y); chunks are `ceil(canvas_size / count)` pixels.
+    Count(UVec2),
+    /// Chunk size in pixels, clamped to the canvas size.
+    ///
+    /// Widths that are a multiple of 64 px match the 256-byte upload row alignment, so uploads need no padding.
+    Size(UVec2),
+}
+
+impl Default for CanvasChunking {
+    fn default() -> Self {
+        Self::Size(DEFAULT_CHUNK_SIZE)
+    }
+}
+
+/// Corner of the canvas that pixel (0, 0) is drawn at; canvas Y increases away from it.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CanvasOrigin {
+    /// Y increases upwards, like Bevy world coordinates.
+    #[default]
+    BottomLeft,
+    /// Y increases downwards, like image and screen coordinates.
+    TopLeft,
+}
+
+/// A point of the canvas: where it sits on the canvas entity (`CanvasConfig::anchor`), or what stays fixed during a
+/// `ResizeCanvas`.
+///
+/// Anchors are as drawn, whatever the `CanvasOrigin`. When resizing, the anchor at pixel (0, 0) (`BottomLeft`, or
+/// `TopLeft` for a `CanvasOrigin::TopLeft` canvas) keeps canvas coordinates unchanged.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CanvasAnchor {
+    /// Bottom-left corner.
+    #[default]
+    BottomLeft,
+    /// Bottom edge, centred horizontally.
+    Bottom,
+    /// Bottom-right corner.
+    BottomRight,
+    /// Left edge, centred vertically.
+    Left,
+    /// Canvas centre.
+    Centre,
+    /// Right edge, centred vertically.
+    Right,
+    /// Top-left corner.
+    TopLeft,
+    /// Top edge, centred horizontally.
+    Top,
+    /// Top-right corner.
+    TopRight,
+}
+
+impl CanvasAnchor {
+    /// Anchor position in half-steps of the canvas size: 0 = bottom/left, 1 = centre, 2 = top/right.
+    #[inline]
+    fn halves(self) -> IVec2 {
+        match self {
+            Self::BottomLeft => IVec2::new(0, 0),
+            Self::Bottom => IVec2::new(1, 0),
+            Self::BottomRight => IVec2::new(2, 0),
+            Self::Left => IVec2::new(0, 1),
+            Self::Centre => IVec2::new(1, 1),
+            Self::Right => IVec2::new(2, 1),
+            Self::TopLeft => IVec2::new(0, 2),
+            Self::Top => IVec2::new(1, 2),
+            Self::TopRight => IVec2::new(2, 2),
+        }
+    }
+
+    /// Anchor position as a fraction of the canvas size, from the bottom-left corner.
+    #[inline]
+    pub fn fraction(self) -> Vec2 {
+        self.halves().as_vec2() / 2.0
+    }
+
+    /// Offset that moves old canvas coordinates to new ones when resizing a bottom-left origin canvas from `old` to `new`.
+    #[inline]
+    pub fn offset(self, old: UVec2, new: UVec2) -> IVec2 {
+        self.offset_for_origin(CanvasOrigin::BottomLeft, old, new)
+    }
+
+    /// Offset that moves old canvas coordinates to new ones when resizing from `old` to `new` with the given origin.
+    #[inline]
+    pub fn offset_for_origin(self, origin: CanvasOrigin, old: UVec2, new: UVec2) -> IVec2 {
+        let mut halves = self.halves();
+        if origin == CanvasOrigin::TopLeft {
+            halves.y = 2 - halves.y;
+        }
+        ((new.as_ivec2() - old.as_ivec2()) * halves).div_euclid(IVec2::splat(2))
+    }
+}
+
+/// How a draw operation resolves pixels outside the canvas on one axis.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum WrapMode {
+    /// Wrap toroidally to the opposite edge.
+    #[default]
+    Wrap,
+    /// Discard pixels outside the canvas.
+    Clip,
+    /// Clamp to the nearest edge pixel, so every pixel past an edge is written to it in turn.
+    Clamp,
+    /// Reflect back across the edge (`size` maps to `size - 1`, `-1` to `0`).
+    Mirror,
+}
+
+impl WrapMode {
+    /// Resolve a coordinate on an axis of `size` pixels, or `None` if it is clipped.
+    #[inline]
+    pub fn resolve(self, coord: i64, size: u32) -> Option<u32> {
+        let size = size as i64;
+        match self {
+            Self::Wrap => Some(coord.rem_euclid(size) as u32),
+            Self::Clip => (0..size).contains(&coord).then_some(coord as u32),
+            Self::Clamp => Some(coord.clamp(0, size - 1) as u32),
+            Self::Mirror => {
+                let reflected = coord.rem_euclid(2 * size);
+                Some(if reflected < size {
+                    reflected
+                } else {
+                    2 * size - 1 - reflected
+                } as u32)
+            }
+        }
+    }
+}
+
+/// Per-axis `WrapMode`s for a canvas.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CanvasWrap {
+    /// Mode for canvas X.
+    pub x: WrapMode,
+    /// Mode for canvas Y.
+    pub y: WrapMode,
+}
+
+impl CanvasWrap {
+    /// Use the same mode on both axes.
+    #[inline]
+    pub const fn splat(mode: WrapMode) -> Self {
+        Self { x: mode, y: mode }
+    }
+
+    /// Resolve a position on a canvas of `size` pixels, or `None` if it is clipped on either axis.
+    #[inline]
+    pub fn resolve(self, pos: I64Vec2, size: UVec2) -> Option<UVec2> {
+        Some(UVec2::new(self.x.resolve(pos.x, size.x)?, self.y.resolve(pos.y, size.y)?))
+    }
+}
+
+/// CPU storage format for canvas pixels.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CanvasPixelFormat {
+    /// Packed RGBA8 `u32` per pixel.
+    #[default]
+    Rgba8,
+    /// One `u8` palette index per pixel, resolved through `CanvasPalette` at upload time.
+    ///
+    /// Colours passed to draw messages (and the clear colour) are interpreted as palette indices,
+    /// only the low 8 bits are stored.
+    Indexed8,
+}
+
+/// GPU texture format of the chunk images.
+///
+/// CPU pixels are always packed RGBA8; uploads swizzle them to the texture's byte order.
+/// The format also sets the colour space that `pack_colour` encodes into.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CanvasTextureFormat {
+    /// sRGB-encoded RGBA8, decoded to linear when sampled (for colour images).
+    #[default]
+    Rgba8UnormSrgb,
+    /// Linear RGBA8, sampled as stored (for data textures).
+    Rgba8Unorm,
+    /// sRGB-encoded BGRA8.
+    Bgra8UnormSrgb,
+}
+
+impl CanvasTextureFormat {
+    /// The wgpu texture format.
+    #[inline]
+    pub fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::Rgba8UnormSrgb => TextureFormat::Rgba8UnormSrgb,
+            Self::Rgba8Unorm => TextureFormat::Rgba8Unorm,
+            Self::Bgra8UnormSrgb => TextureFormat::Bgra8UnormSrgb,
+        }
+    }
+
+    /// Whether pixel bytes are sRGB-encoded.
+    #[inline]
+    pub fn is_srgb(self) -> bool {
+        match self {
+            Self::Rgba8UnormSrgb | Self::Bgra8UnormSrgb => true,
+            Self::Rgba8Unorm => false,
+        }
+    }
+
+    /// Whether the texture stores red and blue swapped relative to the packed RGBA8 pixels.
+    #[inline]
+    pub fn is_bgra(self) -> bool {
+        self == Self::Bgra8UnormSrgb
+    }
+}
+
+/// CPU buffer layout for canvas pixels.
+///
+/// Dirty tracking and GPU uploads are per chunk in both layouts.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CanvasStorage {
+    /// One row-major buffer per chunk (stride = chunk width).
+    #[default]
+    Chunked,
+    /// One row-major canvas-sized buffer (stride = canvas width), so pixels can be indexed as `y * width + x`.
+    Contiguous,
 }
 
 impl CanvasConfig {
+    /// Maximum number of chunks in a canvas's chunk grid (1,048,576, e.g. 1024x1024).
+    ///
+    /// Very large canvases need chunks big enough to stay under it: a 32768x32768 canvas needs chunks of at least
+    /// 32x32 pixels.
+    pub const MAX_TOTAL_CHUNKS: usize = MAX_TOTAL_CHUNKS;
+
     /// Construct a new configuration with validation.
     ///
-    /// # Panics / Debug asserts
-    /// - `canvas_size` must be non-zero in both axes
-    /// - `chunks` must be non-zero in both axes
-    /// - Each axis must be exactly divisible by the corresponding chunk count
-    pub fn new(clear_colour: u32, canvas_z_index: f32, canvas_size: UVec2, chunks: U8Vec2) -> Self {
-        debug_assert!(canvas_size.x > 0);
-        debug_assert!(canvas_size.y > 0);
-        debug_assert!(chunks.x > 0);
-        debug_assert!(chunks.y > 0);
-        debug_assert!(canvas_size.x.is_multiple_of(chunks.x as u32));
-        debug_assert!(canvas_size.y.is_multiple_of(chunks.y as u32));
+    /// # Panics
+    /// If the configuration is invalid (see `try_new`).
+    pub fn new(clear_colour: u32, canvas_z_index: f32, canvas_size: UVec2, chunking: CanvasChunking) -> Self {
+        Self::try_new(clear_colour, canvas_z_index, canvas_size, chunking).unwrap_or_else(|error| panic!("{error}"))
+    }
 
-        Self {
+    /// Construct a new configuration, returning an error if it is invalid (see `validate`).
+    pub fn try_new(
+        clear_colour: u32,
+        canvas_z_index: f32,
+        canvas_size: UVec2,
+        chunking: CanvasChunking,
+    ) -> Result<Self, CanvasConfigError> {
+        let config = Self {
             clear_colour,
             canvas_z_index,
             canvas_size,
-            num_chunks: chunks,
+            chunking,
+            origin: CanvasOrigin::default(),
+            anchor: DEFAULT_CANVAS_ANCHOR,
+            pixel_scale: DEFAULT_PIXEL_SCALE,
+            wrap: CanvasWrap::default(),
+            pixel_format: CanvasPixelFormat::default(),
+            storage: CanvasStorage::default(),
+            texture_format: CanvasTextureFormat::default(),
+            quantiser: None,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the configuration against the default GPU texture size limit.
+    ///
+    /// The fields are public, so configs built with struct literals should be validated before use;
+    /// `CanvasPlugin` does this when it is built.
+    pub fn validate(&self) -> Result<(), CanvasConfigError> {
+        self.validate_with_limit(DEFAULT_MAX_TEXTURE_DIMENSION)
+    }
+
+    /// Check the configuration, with chunk images limited to `max_texture_dimension` pixels per axis.
+    pub fn validate_with_limit(&self, max_texture_dimension: u32) -> Result<(), CanvasConfigError> {
+        if self.canvas_size.cmpeq(UVec2::ZERO).any() {
+            return Err(CanvasConfigError::ZeroCanvasSize);
+        }
+
+        let (CanvasChunking::Count(chunks) | CanvasChunking::Size(chunks)) = self.chunking;
+        if chunks.cmpeq(UVec2::ZERO).any() {
+            return Err(CanvasConfigError::ZeroChunks);
+        }
+
+        // Ceil-sized chunks can cover the canvas in fewer chunks than requested (e.g. 10 px into 6 chunks)
+        let num_chunks = self.num_chunks();
+        if let CanvasChunking::Count(requested) = self.chunking
+            && requested != num_chunks
+        {
+            return Err(CanvasConfigError::UnevenChunks {
+                requested,
+                actual: num_chunks,
+            });
+        }
+
+        let total_chunks = self.total_chunks();
+        if total_chunks > MAX_TOTAL_CHUNKS {
+            return Err(CanvasConfigError::TooManyChunks {
+                total: total_chunks,
+                max: MAX_TOTAL_CHUNKS,
+            });
+        }
+
+        let chunk_size = self.chunk_size();
+        if chunk_size.max_element() > max_texture_dimension {
+            return Err(CanvasConfigError::ChunkTooLarge {
+                chunk_size,
+                max: max_texture_dimension,
+            });
+        }
+
+        if !(self.pixel_scale.is_finite() && self.pixel_scale.cmpgt(Vec2::ZERO).all()) {
+            return Err(CanvasConfigError::InvalidPixelScale);
         }
+
+        if self.quantiser.as_ref().is_some_and(|quantiser| quantiser.palette.is_empty()) {
+            return Err(CanvasConfigError::EmptyQuantiserPalette);
+        }
+
+        Ok(())
     }
 
     /// Clear colour (packed RGBA8).
@@ -53,7 +381,7 @@ impl CanvasConfig {
         self.clear_colour
     }
 
-    /// Z-index used for the canvas sprites.
+    /// Z offset of the chunk sprites relative to the canvas entity.
     #[inline]
     pub fn canvas_z_index(&self) -> f32 {
         self.canvas_z_index
@@ -65,22 +393,158 @@ impl CanvasConfig {
         self.canvas_size
     }
 
+    /// Chunking mode.
+    #[inline]
+    pub fn chunking(&self) -> CanvasChunking {
+        self.chunking
+    }
+
+    /// Corner of the canvas that pixel (0, 0) is drawn at.
+    #[inline]
+    pub fn origin(&self) -> CanvasOrigin {
+        self.origin
+    }
+
+    /// Point of the canvas placed at the canvas entity's origin.
+    #[inline]
+    pub fn anchor(&self) -> CanvasAnchor {
+        self.anchor
+    }
+
+    /// Size of one pixel in the canvas entity's local units.
+    #[inline]
+    pub fn pixel_scale(&self) -> Vec2 {
+        self.pixel_scale
+    }
+
+    /// Per-axis wrap modes used by draw operations.
+    #[inline]
+    pub fn wrap(&self) -> CanvasWrap {
+        self.wrap
+    }
+
+    /// Canvas size in the canvas entity's local units.
+    #[inline]
+    pub fn local_size(&self) -> Vec2 {
+        self.canvas_size.as_vec2() * self.pixel_scale
+    }
+
+    /// Convert a canvas position in pixels to the canvas entity's local space.
+    ///
+    /// Pixel `p` covers `p..p + 1`, so its centre is at `p + 0.5`.
+    #[inline]
+    pub fn pixel_to_local(&self, pos: Vec2) -> Vec2 {
+        let size = self.canvas_size.as_vec2();
+        let from_bottom_left = match self.origin {
+            CanvasOrigin::BottomLeft => pos,
+            CanvasOrigin::TopLeft => Vec2::new(pos.x, size.y - pos.y),
+        };
+        (from_bottom_left - size * self.anchor.fraction()) * self.pixel_scale
+    }
+
+    /// Convert a point in the canvas entity's local space to a canvas position in pixels (inverse of `pixel_to_local`).
+    #[inline]
+    pub fn local_to_pixel(&self, local: Vec2) -> Vec2 {
+        let size = self.canvas_size.as_vec2();
+        let from_bottom_left = local / self.pixel_scale + size * self.anchor.fraction();
+        match self.origin {
+            CanvasOrigin::BottomLeft => from_bottom_left,
+            CanvasOrigin::TopLeft => Vec2::new(from_bottom_left.x, size.y - from_bottom_left.y),
+        }
+    }
+
+    /// Pixel containing a point in the canvas entity's local space, or `None` if it is outside the canvas.
+    #[inline]
+    pub fn local_to_pixel_pos(&self, local: Vec2) -> Option<UVec2> {
+        let pos = self.local_to_pixel(local).floor();
+        (pos.cmpge(Vec2::ZERO).all() && pos.cmplt(self.canvas_size.as_vec2()).all()).then(|| pos.as_uvec2())
+    }
+
+    /// Local scale of the chunk sprites.
+    /// Image rows run top to bottom, so bottom-left origin canvases flip the sprites on Y.
+    #[inline]
+    pub(crate) fn sprite_scale(&self) -> Vec3 {
+        match self.origin {
+            CanvasOrigin::BottomLeft => Vec3::new(self.pixel_scale.x, -self.pixel_scale.y, 1.0),
+            CanvasOrigin::TopLeft => self.pixel_scale.extend(1.0),
+        }
+    }
+
     /// Chunk grid resolution.
     #[inline]
-    pub fn num_chunks(&self) -> U8Vec2 {
-        self.num_chunks
+    pub fn num_chunks(&self) -> UVec2 {
+        self.layout().num_chunks()
+    }
+
+    /// CPU pixel storage format.
+    #[inline]
+    pub fn pixel_format(&self) -> CanvasPixelFormat {
+        self.pixel_format
+    }
+
+    /// CPU buffer layout.
+    #[inline]
+    pub fn storage(&self) -> CanvasStorage {
+        self.storage
+    }
+
+    /// GPU texture format of the chunk images.
+    #[inline]
+    pub fn texture_format(&self) -> CanvasTextureFormat {
+        self.texture_format
     }
 
-    /// Size of one chunk in pixels.
+    /// Pack a colour into an RGBA8 pixel, encoded in the texture format's colour space
+    /// (sRGB bytes for sRGB formats, linear bytes for `Rgba8Unorm`).
+    #[inline]
+    pub fn pack_colour(&self, colour: impl Into<Color>) -> u32 {
+        let colour = colour.into();
+        if self.texture_format.is_srgb() {
+            pack_rgba8(colour.to_srgba().to_u8_array())
+        } else {
+            pack_rgba8(colour.to_linear().to_u8_array())
+        }
+    }
+
+    /// Unpack an RGBA8 pixel into a colour, decoded from the texture format's colour space (inverse of `pack_colour`).
+    #[inline]
+    pub fn unpack_colour(&self, rgba_u32: u32) -> Color {
+        let bytes = unpack_rgba8(rgba_u32);
+        if self.texture_format.is_srgb() {
+            Srgba::from_u8_array(bytes).into()
+        } else {
+            LinearRgba::from_u8_array(bytes).into()
+        }
+    }
+
+    /// Write-stage quantiser, if any.
+    #[inline]
+    pub fn quantiser(&self) -> Option<&CanvasQuantiser> {
+        self.quantiser.as_ref()
+    }
+
+    /// Quantiser used by the run writer.
+    /// Quantisation maps colours, so it has no meaning for palette indices.
+    #[inline]
+    pub(crate) fn write_quantiser(&self) -> Option<&CanvasQuantiser> {
+        match self.pixel_format {
+            CanvasPixelFormat::Rgba8 => self.quantiser(),
+            CanvasPixelFormat::Indexed8 => None,
+        }
+    }
+
+    /// Nominal size of one chunk in pixels; ragged edge chunks are smaller.
     #[inline]
     pub fn chunk_size(&self) -> UVec2 {
-        UVec2::new(
-            self.canvas_size.x / self.num_chunks.x as u32,
-            self.canvas_size.y / self.num_chunks.y as u32,
-        )
+        match self.chunking {
+            CanvasChunking::Count(count) => {
+                UVec2::new(self.canvas_size.x.div_ceil(count.x), self.canvas_size.y.div_ceil(count.y))
+            }
+            CanvasChunking::Size(size) => size.min(self.canvas_size),
+        }
     }
 
-    /// Pixels per chunk.
+    /// Pixels per (full-size) chunk.
     #[inline]
     pub fn pixels_per_chunk(&self) -> usize {
         let chunk_size = self.chunk_size();
@@ -90,17 +554,171 @@ impl CanvasConfig {
     /// Total number of chunks.
     #[inline]
     pub fn total_chunks(&self) -> usize {
-        (self.num_chunks.x as usize) * (self.num_chunks.y as usize)
+        let num_chunks = self.num_chunks();
+        (num_chunks.x as usize) * (num_chunks.y as usize)
+    }
+
+    /// Canvas geometry.
+    #[inline]
+    pub(crate) fn layout(&self) -> CanvasLayout {
+        CanvasLayout::new(self.canvas_size, self.chunk_size())
+    }
+}
+
+/// Reason a `CanvasConfig` is invalid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CanvasConfigError {
+    /// `canvas_size` is zero in at least one axis.
+    ZeroCanvasSize,
+    /// The chunk count or chunk size is zero in at least one axis.
+    ZeroChunks,
+    /// The canvas cannot be split into the requested number of chunks.
+    UnevenChunks { requested: UVec2, actual: UVec2 },
+    /// The chunk grid has more than `CanvasConfig::MAX_TOTAL_CHUNKS` chunks.
+    TooManyChunks { total: usize, max: usize },
+    /// A chunk image would exceed the GPU's maximum texture dimension.
+    ChunkTooLarge { chunk_size: UVec2, max: u32 },
+    /// `pixel_scale` is not finite and positive in both axes.
+    InvalidPixelScale,
+    /// The quantiser's palette has no colours.
+    EmptyQuantiserPalette,
+}
+
+impl core::fmt::Display for CanvasConfigError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ZeroCanvasSize => write!(f, "canvas size must be non-zero in both axes"),
+            Self::ZeroChunks => write!(f, "chunk count or size must be non-zero in both axes"),
+            Self::UnevenChunks { requested, actual } => write!(
+                f,
+                "canvas cannot be split into {requested} chunks; chunks of the resulting size only need {actual}"
+            ),
+            Self::TooManyChunks { total, max } => write!(f, "{total} chunks exceeds the maximum of {max}"),
+            Self::ChunkTooLarge { chunk_size, max } => write!(
+                f,
+                "chunk size {chunk_size} exceeds the maximum texture dimension of {max}; use smaller chunks"
+            ),
+            Self::InvalidPixelScale => write!(f, "pixel scale must be finite and positive in both axes"),
+            Self::EmptyQuantiserPalette => write!(f, "quantiser palette must have at least one colour"),
+        }
     }
 }
 
+impl core::error::Error for CanvasConfigError {}
+
 impl Default for CanvasConfig {
     fn default() -> Self {
         Self {
             clear_colour: DEFAULT_CLEAR_COLOUR,
             canvas_z_index: DEFAULT_CANVAS_Z_INDEX,
             canvas_size: DEFAULT_CANVAS_SIZE,
-            num_chunks: DEFAULT_NUM_CHUNKS,
+            chunking: CanvasChunking::default(),
+            origin: CanvasOrigin::default(),
+            anchor: DEFAULT_CANVAS_ANCHOR,
+            pixel_scale: DEFAULT_PIXEL_SCALE,
+            wrap: CanvasWrap::default(),
+            pixel_format: CanvasPixelFormat::default(),
+            storage: CanvasStorage::default(),
+            texture_format: CanvasTextureFormat::default(),
+            quantiser: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_modes_resolve_both_edges() {
+        let coords = [-5, -1, 0, 3, 4, 8];
+        let cases = [
+            (WrapMode::Wrap, [Some(3), Some(3), Some(0), Some(3), Some(0), Some(0)]),
+            (WrapMode::Clip, [None, None, Some(0), Some(3), None, None]),
+            (WrapMode::Clamp, [Some(0), Some(0), Some(0), Some(3), Some(3), Some(3)]),
+            (WrapMode::Mirror, [Some(3), Some(0), Some(0), Some(3), Some(3), Some(0)]),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(coords.map(|coord| mode.resolve(coord, 4)), expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn canvas_wrap_resolves_per_axis() {
+        let wrap = CanvasWrap {
+            x: WrapMode::Clip,
+            y: WrapMode::Wrap,
+        };
+        assert_eq!(wrap.resolve(I64Vec2::new(1, -1), UVec2::new(4, 2)), Some(UVec2::new(1, 1)));
+        assert_eq!(wrap.resolve(I64Vec2::new(-1, 1), UVec2::new(4, 2)), None);
+    }
+
+    #[test]
+    fn empty_quantiser_palette_is_rejected() {
+        let config = CanvasConfig {
+            quantiser: Some(CanvasQuantiser::new(Vec::new(), Default::default())),
+            ..default()
+        };
+        assert_eq!(config.validate(), Err(CanvasConfigError::EmptyQuantiserPalette));
+    }
+
+    #[test]
+    fn validation_errors() {
+        let config = |canvas_size, chunking| CanvasConfig {
+            canvas_size,
+            chunking,
+            ..default()
+        };
+        assert_eq!(
+            config(UVec2::new(0, 4), CanvasChunking::default()).validate(),
+            Err(CanvasConfigError::ZeroCanvasSize)
+        );
+        assert_eq!(
+            config(UVec2::splat(32768), CanvasChunking::Size(UVec2::splat(16))).validate(),
+            Err(CanvasConfigError::TooManyChunks {
+                total: 2048 * 2048,
+                max: CanvasConfig::MAX_TOTAL_CHUNKS
+            })
+        );
+        assert_eq!(
+            config(UVec2::splat(32768), CanvasChunking::Size(UVec2::splat(32))).validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn anchor_fractions() {
+        assert_eq!(CanvasAnchor::BottomLeft.fraction(), Vec2::ZERO);
+        assert_eq!(CanvasAnchor::Centre.fraction(), Vec2::splat(0.5));
+        assert_eq!(CanvasAnchor::Top.fraction(), Vec2::new(0.5, 1.0));
+    }
+
+    #[test]
+    fn anchor_offsets_follow_the_origin() {
+        let (old, new) = (UVec2::splat(4), UVec2::splat(8));
+        let cases = [
+            (CanvasAnchor::BottomLeft, IVec2::ZERO, IVec2::new(0, 4)),
+            (CanvasAnchor::TopLeft, IVec2::new(0, 4), IVec2::ZERO),
+            (CanvasAnchor::Centre, IVec2::splat(2), IVec2::splat(2)),
+            (CanvasAnchor::TopRight, IVec2::splat(4), IVec2::new(4, 0)),
+            (CanvasAnchor::Bottom, IVec2::new(2, 0), IVec2::new(2, 4)),
+        ];
+        for (anchor, bottom_left, top_left) in cases {
+            assert_eq!(anchor.offset(old, new), bottom_left, "{anchor:?}");
+            assert_eq!(
+                anchor.offset_for_origin(CanvasOrigin::TopLeft, old, new),
+                top_left,
+                "{anchor:?}"
+            );
+            // Shrinking back undoes the offset
+            assert_eq!(anchor.offset(new, old), -bottom_left, "{anchor:?}");
         }
+
+        // Odd growth rounds towards the bottom-left
+        assert_eq!(CanvasAnchor::Centre.offset(UVec2::splat(4), UVec2::splat(7)), IVec2::ONE);
+        assert_eq!(
+            CanvasAnchor::Centre.offset(UVec2::splat(7), UVec2::splat(4)),
+            IVec2::splat(-2)
+        );
     }
 }
@@ -1,18 +1,33 @@
 mod components;
 mod config;
 mod messages;
+mod options;
+mod params;
 mod plugin;
+mod quantise;
 mod resources;
 mod settings;
+mod shapes;
 mod systems;
 mod types;
 mod utils;
+mod writer;
 
 pub mod prelude {
     pub use super::{
-        config::CanvasConfig,
-        messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawSpan},
-        plugin::CanvasPlugin,
+        components::{Canvas, PrimaryCanvas},
+        config::{
+            CanvasAnchor, CanvasChunking, CanvasConfig, CanvasConfigError, CanvasOrigin, CanvasPixelFormat, CanvasStorage,
+            CanvasTextureFormat, CanvasWrap, WrapMode,
+        },
+        messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawShape, DrawSpan, ResizeCanvas},
+        options::{ChannelMask, DrawOptions, RasterOp},
+        params::{CanvasPainter, CanvasReader, CanvasRowsMut},
+        plugin::{CanvasPlugin, SimGridPlugin},
+        quantise::{CanvasQuantiser, Dither},
+        resources::{CanvasPalette, SimCells, SimGrid},
+        shapes::CanvasShape,
+        types::ChunkDirty,
         utils::{pack_rgba8, unpack_rgba8},
     };
 }
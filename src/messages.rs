@@ -3,50 +3,103 @@
 //! These messages are consumed by the canvas systems each update,
 //! translated into CPU writes and dirty-rect tracking,
 //! and finally into GPU upload operations.
+//!
+//! Every message carries `DrawOptions` controlling how its pixels combine with the canvas;
+//! construct messages with `..default()` to use a plain overwrite.
+//! Messages target the canvas entity in `canvas`, or the `PrimaryCanvas` when it is `None`.
 
 use bevy::prelude::*;
 
+use super::{
+    config::{CanvasAnchor, CanvasChunking},
+    options::DrawOptions,
+    shapes::CanvasShape,
+};
+
 /// Set all canvas pixels to a single colour.
-#[derive(Message)]
+#[derive(Message, Default)]
 pub struct ClearCanvas {
+    /// Target canvas (`None` for the primary canvas).
+    pub canvas: Option<Entity>,
     /// Colour to clear with.
     pub rgba_u32: u32,
+    /// Write options.
+    pub options: DrawOptions,
 }
 
 /// Draw a single pixel to the canvas.
-#[derive(Message)]
+#[derive(Message, Default)]
 pub struct DrawPixel {
-    /// Canvas coords, bottom-left origin.
-    pub pos: UVec2,
+    /// Target canvas (`None` for the primary canvas).
+    pub canvas: Option<Entity>,
+    /// Canvas coords; positions outside the canvas (including negative ones) are resolved by its `WrapMode`s.
+    pub pos: IVec2,
     /// Pixel colour.
     pub rgba_u32: u32,
+    /// Write options.
+    pub options: DrawOptions,
 }
 
 /// Draw many independent pixels to the canvas.
 ///
 /// `positions.len()` must equal `rgba_u32.len()`.
-#[derive(Message)]
+#[derive(Message, Default)]
 pub struct DrawPixels {
-    pub positions: Vec<UVec2>,
+    pub canvas: Option<Entity>,
+    pub positions: Vec<IVec2>,
     pub rgba_u32: Vec<u32>,
+    pub options: DrawOptions,
 }
 
 /// Draw a rectangular region to the canvas.
 ///
-/// The region will wrap toroidally if it exceeds canvas bounds.
+/// Pixels outside the canvas bounds are resolved by the canvas's `WrapMode`s (toroidal wrap by default).
 /// `rgba_u32` is row-major: index = y*width + x.
-#[derive(Message)]
+#[derive(Message, Default)]
 pub struct DrawRect {
-    pub start: UVec2,
+    pub canvas: Option<Entity>,
+    pub start: IVec2,
     pub size: UVec2,
     pub rgba_u32: Vec<u32>,
+    pub options: DrawOptions,
 }
 
 /// Draw a contiguous row-major stream to the canvas.
 ///
-/// It advances across X, then moves up a row, and wraps at edges.
-#[derive(Message)]
+/// It advances across X, then moves up a row; rows past the canvas edges are resolved by the Y `WrapMode`.
+#[derive(Message, Default)]
 pub struct DrawSpan {
-    pub start: UVec2,
+    pub canvas: Option<Entity>,
+    pub start: IVec2,
     pub rgba_u32: Vec<u32>,
+    pub options: DrawOptions,
+}
+
+/// Fill a shape with a single colour.
+///
+/// Shapes use sub-pixel coordinates and fill the pixels whose centres they contain (see `CanvasShape`).
+/// Pixels outside the canvas bounds are resolved by the canvas's `WrapMode`s.
+#[derive(Message, Default)]
+pub struct DrawShape {
+    pub canvas: Option<Entity>,
+    pub shape: CanvasShape,
+    pub rgba_u32: u32,
+    pub options: DrawOptions,
+}
+
+/// Resize and/or re-chunk the canvas at runtime.
+///
+/// Rebuilds the CPU buffers, dirty tracking, chunk images and sprites, and frees the old images.
+/// Existing content is kept where it overlaps the new canvas, positioned by `anchor`.
+/// If several are sent to one canvas in an update, only the last is applied.
+#[derive(Message, Default)]
+pub struct ResizeCanvas {
+    /// Target canvas (`None` for the primary canvas).
+    pub canvas: Option<Entity>,
+    /// New canvas size in pixels.
+    pub size: UVec2,
+    /// New chunk count or size.
+    pub chunking: CanvasChunking,
+    /// Which part of the canvas stays fixed when it grows or shrinks.
+    pub anchor: CanvasAnchor,
 }
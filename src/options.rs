@@ -0,0 +1,359 @@
+//! Per-draw write options.
+//!
+//! Options are carried by each draw message and applied by the run writer
+//! as pixels are copied into the CPU chunks.
+
+use core::ops::BitOr;
+
+/// Selects which RGBA8 channels a draw writes.
+///
+/// Unselected channels keep their existing chunk contents.
+/// The mask is stored in packed pixel layout, so for indexed canvases
+/// the red channel covers the palette index.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChannelMask(u32);
+
+impl ChannelMask {
+    /// No channels; the draw leaves pixels untouched.
+    pub const NONE: Self = Self(0x0000_0000);
+    /// Red channel only.
+    pub const R: Self = Self(0x0000_00ff);
+    /// Green channel only.
+    pub const G: Self = Self(0x0000_ff00);
+    /// Blue channel only.
+    pub const B: Self = Self(0x00ff_0000);
+    /// Alpha channel only.
+    pub const A: Self = Self(0xff00_0000);
+    /// Colour channels, preserving alpha.
+    pub const RGB: Self = Self(0x00ff_ffff);
+    /// All channels (the default).
+    pub const ALL: Self = Self(0xffff_ffff);
+
+    /// Build a mask from per-channel flags.
+    #[inline]
+    pub const fn from_channels(r: bool, g: bool, b: bool, a: bool) -> Self {
+        let mut bits = 0;
+        if r {
+            bits |= Self::R.0;
+        }
+        if g {
+            bits |= Self::G.0;
+        }
+        if b {
+            bits |= Self::B.0;
+        }
+        if a {
+            bits |= Self::A.0;
+        }
+        Self(bits)
+    }
+
+    /// Mask as packed pixel bits.
+    #[inline(always)]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for ChannelMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for ChannelMask {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Bitwise raster operation between the source and the existing destination pixel.
+///
+/// Operations act on the whole packed `u32` (or palette index), before the channel mask is applied.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum RasterOp {
+    /// `src` (plain overwrite).
+    #[default]
+    Copy,
+    /// `dst & src`.
+    And,
+    /// `dst | src`.
+    Or,
+    /// `dst ^ src`.
+    Xor,
+    /// `!dst`; the source is ignored.
+    Not,
+    /// `dst & !src`; clears the source bits.
+    AndNot,
+}
+
+impl RasterOp {
+    /// Combine one source pixel with one destination pixel.
+    #[inline(always)]
+    pub fn apply(self, dst: u32, src: u32) -> u32 {
+        match self {
+            Self::Copy => src,
+            Self::And => dst & src,
+            Self::Or => dst | src,
+            Self::Xor => dst ^ src,
+            Self::Not => !dst,
+            Self::AndNot => dst & !src,
+        }
+    }
+}
+
+/// Options controlling how a draw combines its source pixels with the canvas.
+///
+/// Source pixels are first modulated by `tint` and `opacity`,
+/// then combined with the destination through `op`, and finally written through `mask`.
+/// Modulation only applies to RGBA8 canvases; indexed canvases ignore it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DrawOptions {
+    /// Channels written by the draw.
+    pub mask: ChannelMask,
+    /// Raster operation combining source and destination.
+    pub op: RasterOp,
+    /// Multiplier for source alpha, in `[0, 1]`.
+    pub opacity: f32,
+    /// Packed RGBA8 colour multiplied into every source pixel (white leaves it unchanged).
+    pub tint: u32,
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        Self {
+            mask: ChannelMask::ALL,
+            op: RasterOp::Copy,
+            opacity: 1.0,
+            tint: 0xffff_ffff,
+        }
+    }
+}
+
+impl DrawOptions {
+    /// True if the source can be copied straight into the chunk.
+    #[inline(always)]
+    pub fn is_copy(&self) -> bool {
+        self.mask == ChannelMask::ALL && self.op == RasterOp::Copy && self.modulation().is_none()
+    }
+
+    /// Combine one source pixel with the existing destination pixel.
+    #[inline(always)]
+    pub fn apply(&self, dst: u32, src: u32) -> u32 {
+        self.apply_index(dst, self.modulate(src))
+    }
+
+    /// Apply tint and opacity to a source pixel.
+    #[inline(always)]
+    pub fn modulate(&self, src: u32) -> u32 {
+        match self.modulation() {
+            Some(modulation) => modulation.apply(src),
+            None => src,
+        }
+    }
+
+    /// Copy of these options with tint and opacity reset, for sources that are already modulated.
+    #[inline]
+    pub fn without_modulation(&self) -> Self {
+        Self {
+            opacity: 1.0,
+            tint: 0xffff_ffff,
+            ..*self
+        }
+    }
+
+    /// Combine one source value with the destination, ignoring tint and opacity.
+    /// Used for palette indices, where colour modulation has no meaning.
+    #[inline(always)]
+    pub fn apply_index(&self, dst: u32, src: u32) -> u32 {
+        let mask = self.mask.bits();
+        (dst & !mask) | (self.op.apply(dst, src) & mask)
+    }
+
+    /// Combine a run of source pixels into `dst`.
+    #[inline]
+    pub fn apply_run(&self, dst: &mut [u32], src: &[u32]) {
+        debug_assert_eq!(dst.len(), src.len());
+
+        match self.modulation() {
+            Some(modulation) => combine_run(dst, src, self.mask.bits(), self.op, |s| modulation.apply(s)),
+            None => combine_run(dst, src, self.mask.bits(), self.op, |s| s),
+        }
+    }
+
+    /// Combine a single source value into every pixel of `dst`.
+    #[inline]
+    pub fn apply_fill(&self, dst: &mut [u32], value: u32) {
+        let value = self.modulate(value);
+
+        let mask = self.mask.bits();
+        let op = self.op;
+        for d in dst.iter_mut() {
+            *d = (*d & !mask) | (op.apply(*d, value) & mask);
+        }
+    }
+
+    /// Per-channel source multipliers, or `None` if tint and opacity are identity.
+    #[inline(always)]
+    fn modulation(&self) -> Option<Modulation> {
+        if self.tint == 0xffff_ffff && self.opacity >= 1.0 {
+            return None;
+        }
+        Some(Modulation::new(self.tint, self.opacity))
+    }
+}
+
+/// Integer per-channel multipliers (`0..=255`) derived from tint and opacity.
+#[derive(Clone, Copy)]
+struct Modulation {
+    factors: [u32; 4],
+}
+
+impl Modulation {
+    #[inline]
+    fn new(tint: u32, opacity: f32) -> Self {
+        let [r, g, b, a] = tint.to_le_bytes();
+        let opacity = (opacity.clamp(0.0, 1.0) * 255.0).round() as u32;
+        Self {
+            factors: [r as u32, g as u32, b as u32, mul_u8(a as u32, opacity)],
+        }
+    }
+
+    /// Multiply each channel of a packed RGBA8 pixel by its factor.
+    #[inline(always)]
+    fn apply(&self, src: u32) -> u32 {
+        let [r, g, b, a] = src.to_le_bytes();
+        u32::from_le_bytes([
+            mul_u8(r as u32, self.factors[0]) as u8,
+            mul_u8(g as u32, self.factors[1]) as u8,
+            mul_u8(b as u32, self.factors[2]) as u8,
+            mul_u8(a as u32, self.factors[3]) as u8,
+        ])
+    }
+}
+
+/// Multiply two `0..=255` values, treating 255 as one (rounded).
+#[inline(always)]
+fn mul_u8(a: u32, b: u32) -> u32 {
+    let t = a * b + 128;
+    (t + (t >> 8)) >> 8
+}
+
+/// Apply `op` to each destination/source pair, mapping the source first and writing only the masked bits.
+///
+/// Matching on `op` outside the loop keeps each arm a tight loop over `u32`s.
+#[inline(always)]
+fn combine_run(dst: &mut [u32], src: &[u32], mask: u32, op: RasterOp, map: impl Fn(u32) -> u32) {
+    match op {
+        RasterOp::Copy => combine_each(dst, src, mask, |_, s| map(s)),
+        RasterOp::And => combine_each(dst, src, mask, |d, s| d & map(s)),
+        RasterOp::Or => combine_each(dst, src, mask, |d, s| d | map(s)),
+        RasterOp::Xor => combine_each(dst, src, mask, |d, s| d ^ map(s)),
+        RasterOp::Not => combine_each(dst, src, mask, |d, _| !d),
+        RasterOp::AndNot => combine_each(dst, src, mask, |d, s| d & !map(s)),
+    }
+}
+
+/// Apply `f` to each destination/source pair, writing only the masked bits.
+#[inline(always)]
+fn combine_each(dst: &mut [u32], src: &[u32], mask: u32, f: impl Fn(u32, u32) -> u32) {
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d = (*d & !mask) | (f(*d, s) & mask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_u8_rounds_exactly() {
+        for a in 0..=255 {
+            for b in 0..=255 {
+                let expected = (a * b) as f64 / 255.0;
+                assert_eq!(mul_u8(a, b), expected.round() as u32, "{a} * {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn masked_channels_are_preserved() {
+        let dst = 0x4433_2211;
+        let src = 0xddcc_bbaa;
+        let cases = [
+            (ChannelMask::NONE, 0x4433_2211),
+            (ChannelMask::R, 0x4433_22aa),
+            (ChannelMask::G | ChannelMask::A, 0xdd33_bb11),
+            (ChannelMask::RGB, 0x44cc_bbaa),
+            (ChannelMask::from_channels(false, false, true, false), 0x44cc_2211),
+            (ChannelMask::ALL, 0xddcc_bbaa),
+        ];
+        for (mask, expected) in cases {
+            let options = DrawOptions {
+                mask,
+                ..Default::default()
+            };
+            let mut run = [dst; 3];
+            options.apply_run(&mut run, &[src; 3]);
+            assert_eq!(run, [expected; 3], "run {mask:?}");
+            let mut fill = [dst; 3];
+            options.apply_fill(&mut fill, src);
+            assert_eq!(fill, [expected; 3], "fill {mask:?}");
+        }
+    }
+
+    #[test]
+    fn raster_ops_match_their_documented_results() {
+        let dst = 0x0ff0_f00f;
+        let src = 0x00ff_ff00;
+        let cases = [
+            (RasterOp::Copy, src),
+            (RasterOp::And, 0x00f0_f000),
+            (RasterOp::Or, 0x0fff_ff0f),
+            (RasterOp::Xor, 0x0f0f_0f0f),
+            (RasterOp::Not, 0xf00f_0ff0),
+            (RasterOp::AndNot, 0x0f00_000f),
+        ];
+        for (op, expected) in cases {
+            assert_eq!(op.apply(dst, src), expected, "{op:?}");
+
+            let options = DrawOptions {
+                op,
+                ..Default::default()
+            };
+            let mut run = [dst; 3];
+            options.apply_run(&mut run, &[src; 3]);
+            assert_eq!(run, [expected; 3], "run {op:?}");
+            let mut fill = [dst; 3];
+            options.apply_fill(&mut fill, src);
+            assert_eq!(fill, [expected; 3], "fill {op:?}");
+
+            // The op still only writes through the mask
+            let options = DrawOptions {
+                op,
+                mask: ChannelMask::R,
+                ..Default::default()
+            };
+            let mut run = [dst; 3];
+            options.apply_run(&mut run, &[src; 3]);
+            assert_eq!(run, [(dst & !0xff) | (expected & 0xff); 3], "masked {op:?}");
+        }
+    }
+
+    #[test]
+    fn not_ignores_the_source() {
+        let options = DrawOptions {
+            op: RasterOp::Not,
+            ..Default::default()
+        };
+        let mut run = [0x1234_5678, 0xffff_0000];
+        options.apply_run(&mut run, &[0xdead_beef, 0]);
+        assert_eq!(run, [!0x1234_5678, 0x0000_ffff]);
+        let mut fill = [0x1234_5678];
+        options.apply_fill(&mut fill, 0xdead_beef);
+        assert_eq!(fill, [!0x1234_5678]);
+    }
+}
@@ -0,0 +1,894 @@
+//! System parameters for direct access to the canvas CPU buffers.
+//!
+//! These use the same canvas coordinates as the draw messages. Writes resolve pixels outside the canvas by the
+//! canvas's `WrapMode`s, while reads and in-place region access always wrap toroidally.
+//! Each targets the single canvas matching its query filter, the `PrimaryCanvas` by default
+//! (e.g. `CanvasPainter<With<Minimap>>` for another); systems using them are skipped while no canvas matches.
+
+use core::ops::Range;
+
+use bevy::{
+    ecs::{query::QueryFilter, system::SystemParam},
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+
+use super::{
+    components::{Canvas, PrimaryCanvas},
+    config::CanvasPixelFormat,
+    options::DrawOptions,
+    resources::CanvasDirtyRects,
+    shapes::CanvasShape,
+    types::{CanvasLayout, ChunkDirty, ChunkPixels, MOORE, PixelSliceMut, VON_NEUMANN, mark_chunk_rects, wrap_range},
+    writer::CanvasWriter,
+};
+
+/// Read-only access to the pixels of the canvas matching `Filter` (the primary canvas by default).
+///
+/// Values are packed RGBA8, or palette indices widened to `u32` for indexed canvases.
+/// Reads see every write made by draw messages in previous updates.
+#[derive(SystemParam)]
+pub struct CanvasReader<'w, 's, Filter: QueryFilter + 'static = With<PrimaryCanvas>> {
+    canvas: Single<'w, 's, &'static Canvas, Filter>,
+}
+
+impl<Filter: QueryFilter> CanvasReader<'_, '_, Filter> {
+    /// Total canvas size in pixels.
+    #[inline]
+    pub fn canvas_size(&self) -> UVec2 {
+        self.canvas.config.canvas_size()
+    }
+
+    /// Unpack a pixel into a colour, in the canvas's colour space (see `CanvasConfig::unpack_colour`).
+    #[inline]
+    pub fn unpack_colour(&self, rgba_u32: u32) -> Color {
+        self.canvas.config.unpack_colour(rgba_u32)
+    }
+
+    /// Read a single pixel, wrapping toroidally.
+    #[inline]
+    pub fn get_pixel(&self, pos: UVec2) -> u32 {
+        let (buffer_index, index) = self.canvas.cpu.locate(self.layout().wrap(pos));
+        self.canvas.cpu.buffer(buffer_index).get(index)
+    }
+
+    /// The 8 Moore neighbours of a pixel, wrapping toroidally.
+    ///
+    /// Order is row-major from `(-1, -1)` to `(1, 1)`, skipping the centre.
+    #[inline]
+    pub fn moore(&self, pos: UVec2) -> [u32; 8] {
+        self.neighbours(pos, &MOORE, 1)
+    }
+
+    /// The 4 von Neumann neighbours of a pixel, wrapping toroidally.
+    ///
+    /// Order is `(0, -1)`, `(-1, 0)`, `(1, 0)`, `(0, 1)`.
+    #[inline]
+    pub fn von_neumann(&self, pos: UVec2) -> [u32; 4] {
+        self.neighbours(pos, &VON_NEUMANN, 1)
+    }
+
+    /// Read the square window of `radius` around a pixel into a new buffer, wrapping toroidally.
+    ///
+    /// The window is `2 * radius + 1` pixels wide, row-major from `(-radius, -radius)`.
+    pub fn window(&self, pos: UVec2, radius: u32) -> Vec<u32> {
+        let side = (2 * radius + 1) as usize;
+        let mut pixels = vec![0; side * side];
+        self.window_into(pos, radius, &mut pixels);
+        pixels
+    }
+
+    /// Read the square window of `radius` around a pixel into `dst`, wrapping toroidally.
+    ///
+    /// `dst.len()` must equal `(2 * radius + 1)^2`.
+    pub fn window_into(&self, pos: UVec2, radius: u32, dst: &mut [u32]) {
+        let side = 2 * radius + 1;
+        debug_assert_eq!(dst.len(), (side as usize) * (side as usize));
+
+        let layout = self.layout();
+        let pos = layout.wrap(pos);
+
+        // Interior fast path: the window is a plain sub-block of one buffer
+        if let Some((buffer_index, index)) = self.canvas.cpu.locate_interior(pos, radius) {
+            let buffer = self.canvas.cpu.buffer(buffer_index);
+            let stride = self.canvas.cpu.row_stride(buffer_index);
+            let first = index - radius as usize * stride - radius as usize;
+            for (row, dst) in dst.chunks_exact_mut(side as usize).enumerate() {
+                buffer.read(first + row * stride, dst);
+            }
+            return;
+        }
+
+        let start = layout.offset(pos, IVec2::splat(-(radius as i32)));
+        self.read_rect_into(start, UVec2::splat(side), dst);
+    }
+
+    /// The whole canvas as one row-major slice with its stride (the canvas width).
+    ///
+    /// Returns `None` unless the canvas uses `CanvasStorage::Contiguous` with `CanvasPixelFormat::Rgba8`.
+    #[inline]
+    pub fn contiguous(&self) -> Option<(&[u32], usize)> {
+        let stride = self.canvas.cpu.row_stride(0);
+        self.canvas.cpu.contiguous().map(|pixels| (pixels, stride))
+    }
+
+    /// Read a row-major rectangle into a new buffer, wrapping toroidally.
+    pub fn read_rect(&self, start: UVec2, size: UVec2) -> Vec<u32> {
+        let mut pixels = vec![0; (size.x as usize) * (size.y as usize)];
+        self.read_rect_into(start, size, &mut pixels);
+        pixels
+    }
+
+    /// Read a row-major rectangle into `dst`, wrapping toroidally.
+    ///
+    /// `dst.len()` must equal `size.x * size.y`.
+    pub fn read_rect_into(&self, start: UVec2, size: UVec2, dst: &mut [u32]) {
+        debug_assert_eq!(dst.len(), (size.x as usize) * (size.y as usize));
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        // Runs arrive in row-major order, so they fill `dst` front to back
+        let mut dst_start = 0;
+        for (pos, run) in self.layout().rect_runs(start, size) {
+            let (buffer_index, index) = self.canvas.cpu.locate(pos);
+            let run = run as usize;
+            self.canvas
+                .cpu
+                .buffer(buffer_index)
+                .read(index, &mut dst[dst_start..dst_start + run]);
+            dst_start += run;
+        }
+    }
+
+    /// Walk a rectangle row by row, wrapping toroidally, yielding `(canvas_y, x_range, pixels)`.
+    ///
+    /// Each row is split into slices at chunk boundaries and at the canvas edge,
+    /// so `x_range` is always a non-wrapping range of canvas columns matching `pixels`.
+    ///
+    /// # Panics
+    /// If the canvas uses `CanvasPixelFormat::Indexed8`.
+    pub fn rows(&self, start: UVec2, size: UVec2) -> impl Iterator<Item = (u32, Range<u32>, &[u32])> + '_ {
+        assert_eq!(
+            self.canvas.config.pixel_format(),
+            CanvasPixelFormat::Rgba8,
+            "rows requires an Rgba8 canvas"
+        );
+
+        self.layout().rect_runs(start, size).map(|(pos, run)| {
+            let (buffer_index, index) = self.canvas.cpu.locate(pos);
+            let ChunkPixels::Rgba8(data) = self.canvas.cpu.buffer(buffer_index) else {
+                unreachable!();
+            };
+            (pos.y, pos.x..pos.x + run, &data[index..index + run as usize])
+        })
+    }
+
+    /// Walk a rectangle pixel by pixel in row-major order, wrapping toroidally, yielding `(canvas_pos, value)`.
+    pub fn pixels(&self, start: UVec2, size: UVec2) -> impl Iterator<Item = (UVec2, u32)> + '_ {
+        self.layout().rect_runs(start, size).flat_map(|(pos, run)| {
+            let (buffer_index, index) = self.canvas.cpu.locate(pos);
+            let buffer = self.canvas.cpu.buffer(buffer_index);
+            (0..run).map(move |i| (UVec2::new(pos.x + i, pos.y), buffer.get(index + i as usize)))
+        })
+    }
+
+    /// Read pixels at fixed offsets from `pos`; offsets must lie within `radius`.
+    ///
+    /// Interior pixels index the buffer directly, avoiding a chunk lookup per neighbour.
+    #[inline]
+    fn neighbours<const N: usize>(&self, pos: UVec2, offsets: &[IVec2; N], radius: u32) -> [u32; N] {
+        let layout = self.layout();
+        let pos = layout.wrap(pos);
+
+        if let Some((buffer_index, index)) = self.canvas.cpu.locate_interior(pos, radius) {
+            let buffer = self.canvas.cpu.buffer(buffer_index);
+            let stride = self.canvas.cpu.row_stride(buffer_index) as isize;
+            return offsets.map(|d| buffer.get((index as isize + d.y as isize * stride + d.x as isize) as usize));
+        }
+
+        offsets.map(|d| {
+            let (buffer_index, index) = self.canvas.cpu.locate(layout.offset(pos, d));
+            self.canvas.cpu.buffer(buffer_index).get(index)
+        })
+    }
+
+    #[inline]
+    fn layout(&self) -> CanvasLayout {
+        self.canvas.config.layout()
+    }
+}
+
+/// Mutable cross-chunk row iterator returned by `CanvasPainter::rows_mut`.
+///
+/// Yields `(canvas_y, x_range, pixels)`; every yielded slice is marked dirty when the iterator is dropped.
+pub struct CanvasRowsMut<'a> {
+    runs: std::vec::IntoIter<(UVec2, &'a mut [u32])>,
+    yielded: Vec<(UVec2, u32)>,
+    dirty: &'a mut CanvasDirtyRects,
+    layout: CanvasLayout,
+}
+
+impl<'a> Iterator for CanvasRowsMut<'a> {
+    type Item = (u32, Range<u32>, &'a mut [u32]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (pos, pixels) = self.runs.next()?;
+        let run = pixels.len() as u32;
+        self.yielded.push((pos, run));
+        Some((pos.y, pos.x..pos.x + run, pixels))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.runs.size_hint()
+    }
+}
+
+impl Drop for CanvasRowsMut<'_> {
+    fn drop(&mut self) {
+        for &(pos, run) in &self.yielded {
+            let chunk_key = self.layout.chunk_xy(pos);
+            self.dirty
+                .mark_rect(&chunk_key, self.layout.local_xy(pos), UVec2::new(run, 1));
+        }
+    }
+}
+
+/// Immediate-mode write access to the canvas, without going through messages.
+///
+/// Writes go straight into the CPU chunks and are dirty-tracked automatically;
+/// upload ops are still built once per frame in `PostUpdate`, so paint from `Update` or earlier.
+/// The `_with` variants accept the same `DrawOptions` as the draw messages.
+/// Targets the canvas matching `Filter` (the primary canvas by default).
+#[derive(SystemParam)]
+pub struct CanvasPainter<'w, 's, Filter: QueryFilter + 'static = With<PrimaryCanvas>> {
+    canvas: Single<'w, 's, &'static mut Canvas, Filter>,
+}
+
+impl<Filter: QueryFilter> CanvasPainter<'_, '_, Filter> {
+    /// Total canvas size in pixels.
+    #[inline]
+    pub fn canvas_size(&self) -> UVec2 {
+        self.canvas.config.canvas_size()
+    }
+
+    /// Pack a colour into a pixel, in the canvas's colour space (see `CanvasConfig::pack_colour`).
+    #[inline]
+    pub fn pack_colour(&self, colour: impl Into<Color>) -> u32 {
+        self.canvas.config.pack_colour(colour)
+    }
+
+    /// Set a single pixel, resolved by the wrap modes.
+    #[inline]
+    pub fn set_pixel(&mut self, pos: IVec2, rgba_u32: u32) {
+        self.set_pixel_with(pos, rgba_u32, &DrawOptions::default());
+    }
+
+    /// Set a single pixel with write options.
+    #[inline]
+    pub fn set_pixel_with(&mut self, pos: IVec2, rgba_u32: u32, options: &DrawOptions) {
+        self.writer().pixel(pos, rgba_u32, options);
+    }
+
+    /// Fill a rectangle with a single colour, resolved by the wrap modes.
+    #[inline]
+    pub fn fill_rect(&mut self, start: IVec2, size: UVec2, rgba_u32: u32) {
+        self.fill_rect_with(start, size, rgba_u32, &DrawOptions::default());
+    }
+
+    /// Fill a rectangle with a single colour, with write options.
+    ///
+    /// Only the canvas pixels the rectangle resolves to are visited, each once, however large `size` is.
+    pub fn fill_rect_with(&mut self, start: IVec2, size: UVec2, rgba_u32: u32, options: &DrawOptions) {
+        self.writer().fill_rect(start, size, rgba_u32, options);
+    }
+
+    /// Fill a shape with a single colour, resolved by the wrap modes (as `DrawShape`).
+    #[inline]
+    pub fn fill_shape(&mut self, shape: CanvasShape, rgba_u32: u32) {
+        self.fill_shape_with(shape, rgba_u32, &DrawOptions::default());
+    }
+
+    /// Fill a shape with a single colour, with write options.
+    #[inline]
+    pub fn fill_shape_with(&mut self, shape: CanvasShape, rgba_u32: u32, options: &DrawOptions) {
+        if !shape.is_finite() {
+            warn!("CanvasPainter::fill_shape ignored: non-finite shape {shape:?}");
+            return;
+        }
+        self.writer().shape(shape, rgba_u32, options);
+    }
+
+    /// Write a row-major stream starting at `start`, resolved by the wrap modes (as `DrawSpan`).
+    #[inline]
+    pub fn write_span(&mut self, start: IVec2, src_u32: &[u32]) {
+        self.write_span_with(start, src_u32, &DrawOptions::default());
+    }
+
+    /// Write a row-major stream with write options.
+    #[inline]
+    pub fn write_span_with(&mut self, start: IVec2, src_u32: &[u32], options: &DrawOptions) {
+        self.writer().span(start, src_u32, options);
+    }
+
+    /// Copy a row-major rectangle onto the canvas, resolved by the wrap modes (as `DrawRect`).
+    ///
+    /// `src_u32.len()` must equal `size.x * size.y`.
+    #[inline]
+    pub fn blit(&mut self, start: IVec2, size: UVec2, src_u32: &[u32]) {
+        self.blit_with(start, size, src_u32, &DrawOptions::default());
+    }
+
+    /// Copy a row-major rectangle onto the canvas with write options.
+    pub fn blit_with(&mut self, start: IVec2, size: UVec2, src_u32: &[u32], options: &DrawOptions) {
+        let expected = (size.x as usize) * (size.y as usize);
+        if src_u32.len() != expected {
+            warn!(
+                "CanvasPainter::blit length mismatch (expected {}, got {})",
+                expected,
+                src_u32.len()
+            );
+            return;
+        }
+        self.writer().rect(start, size, src_u32, options);
+    }
+
+    /// Run `f` on every chunk in parallel on the `ComputeTaskPool`.
+    ///
+    /// The closure receives the chunk key, the chunk's row-major pixels (stride = chunk width),
+    /// and a `ChunkDirty` handle; only regions it marks are uploaded.
+    /// Writes bypass `DrawOptions` and the quantiser.
+    /// With `CanvasStorage::Contiguous`, each chunk is copied through a scratch buffer (one task per chunk row).
+    ///
+    /// # Panics
+    /// If the canvas uses `CanvasPixelFormat::Indexed8`.
+    pub fn par_for_each_chunk<F>(&mut self, f: F)
+    where
+        F: Fn(UVec2, &mut [u32], &mut ChunkDirty) + Send + Sync,
+    {
+        assert_eq!(
+            self.canvas.config.pixel_format(),
+            CanvasPixelFormat::Rgba8,
+            "par_for_each_chunk requires an Rgba8 canvas"
+        );
+
+        let layout = self.layout();
+        let per_block = self.canvas.cpu.chunks_per_block();
+        let f = &f;
+
+        // Blocks are disjoint, so each task gets exclusive access to its pixels and dirty rects
+        let canvas = &mut **self.canvas;
+        let blocks = canvas
+            .cpu
+            .blocks_mut()
+            .into_iter()
+            .zip(canvas.dirty.rects_mut().chunks_mut(per_block));
+        ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+            for (block, rects) in blocks {
+                let PixelSliceMut::Rgba8(pixels) = block.pixels else {
+                    unreachable!();
+                };
+                let first_xy = block.origin / layout.chunk_size;
+
+                // Chunked storage hands the chunk over directly
+                if per_block == 1 {
+                    scope.spawn(async move {
+                        let mut dirty = ChunkDirty::new(&mut rects[0], block.origin, block.size);
+                        f(first_xy, pixels, &mut dirty);
+                    });
+                    continue;
+                }
+
+                // Contiguous storage: copy each chunk of the band through a chunk-strided scratch buffer
+                let stride = block.stride;
+                scope.spawn(async move {
+                    let mut scratch = Vec::new();
+                    for (i, rect) in rects.iter_mut().enumerate() {
+                        let chunk_xy = first_xy + UVec2::new(i as u32, 0);
+                        let origin = layout.chunk_min(chunk_xy);
+                        let extent = layout.chunk_extent(chunk_xy);
+                        let width = extent.x as usize;
+                        let x0 = origin.x as usize;
+
+                        scratch.clear();
+                        for row in 0..extent.y as usize {
+                            scratch.extend_from_slice(&pixels[row * stride + x0..row * stride + x0 + width]);
+                        }
+
+                        f(chunk_xy, &mut scratch, &mut ChunkDirty::new(rect, origin, extent));
+
+                        for (row, src) in scratch.chunks_exact(width).enumerate() {
+                            pixels[row * stride + x0..row * stride + x0 + width].copy_from_slice(src);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// Rewrite every pixel of a rectangle in place, wrapping toroidally.
+    ///
+    /// `f` receives the wrapped canvas position and the old value, and returns the new value.
+    /// Exactly the visited area is marked dirty; writes bypass `DrawOptions` and the quantiser.
+    /// Pixels are visited row by row from `start`; if `size` exceeds the canvas, each pixel is still visited once.
+    pub fn map_region(&mut self, start: UVec2, size: UVec2, mut f: impl FnMut(UVec2, u32) -> u32) {
+        let layout = self.layout();
+        let size = size.min(layout.canvas_size);
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        for (pos, run) in layout.rect_runs(start, size) {
+            let chunk_key = layout.chunk_xy(pos);
+            let local = layout.local_xy(pos);
+            let (buffer_index, index) = self.canvas.cpu.locate(pos);
+            self.canvas
+                .cpu
+                .buffer_mut(buffer_index)
+                .map_run(index, run as usize, |i, old| f(UVec2::new(pos.x + i as u32, pos.y), old));
+            self.canvas.dirty.mark_rect(&chunk_key, local, UVec2::new(run, 1));
+        }
+    }
+
+    /// Mutable variant of `CanvasReader::rows`, yielding `(canvas_y, x_range, pixels)`.
+    ///
+    /// `size` is limited to the canvas so every pixel is yielded once.
+    /// Rows that were yielded are marked dirty when the iterator is dropped;
+    /// writes bypass `DrawOptions` and the quantiser.
+    ///
+    /// # Panics
+    /// If the canvas uses `CanvasPixelFormat::Indexed8`.
+    pub fn rows_mut(&mut self, start: UVec2, size: UVec2) -> CanvasRowsMut<'_> {
+        assert_eq!(
+            self.canvas.config.pixel_format(),
+            CanvasPixelFormat::Rgba8,
+            "rows_mut requires an Rgba8 canvas"
+        );
+
+        let layout = self.layout();
+        let runs: Vec<(UVec2, u32)> = layout.rect_runs(start, size.min(layout.canvas_size)).collect();
+
+        // Hand out disjoint slices by walking the runs in storage order and splitting the buffers
+        let mut order: Vec<(usize, usize, usize)> = runs
+            .iter()
+            .enumerate()
+            .map(|(slot, &(pos, _))| {
+                let (buffer_index, index) = self.canvas.cpu.locate(pos);
+                (buffer_index, index, slot)
+            })
+            .collect();
+        order.sort_unstable();
+
+        let canvas = &mut **self.canvas;
+        let mut slices: Vec<Option<&mut [u32]>> = (0..runs.len()).map(|_| None).collect();
+        let mut buffers = canvas.cpu.buffers_mut().iter_mut().enumerate();
+        let mut current: Option<(usize, &mut [u32], usize)> = None;
+        for (buffer_index, index, slot) in order {
+            if current.as_ref().is_none_or(|(b, ..)| *b != buffer_index) {
+                let (b, buffer) = buffers.find(|(b, _)| *b == buffer_index).unwrap();
+                let ChunkPixels::Rgba8(data) = buffer else {
+                    unreachable!();
+                };
+                current = Some((b, data.as_mut_slice(), 0));
+            }
+
+            let (_, rest, consumed) = current.as_mut().unwrap();
+            let (_, tail) = core::mem::take(rest).split_at_mut(index - *consumed);
+            let (run, tail) = tail.split_at_mut(runs[slot].1 as usize);
+            *rest = tail;
+            *consumed = index + run.len();
+            slices[slot] = Some(run);
+        }
+
+        CanvasRowsMut {
+            runs: runs
+                .into_iter()
+                .zip(slices)
+                .map(|((pos, _), pixels)| (pos, pixels.unwrap()))
+                .collect::<Vec<_>>()
+                .into_iter(),
+            yielded: Vec::new(),
+            dirty: &mut canvas.dirty,
+            layout,
+        }
+    }
+
+    /// Mutable variant of `CanvasReader::pixels`, yielding `(canvas_pos, &mut value)`.
+    ///
+    /// Marks dirty on drop, as `rows_mut`. Unlike `CanvasReader::pixels`, it needs RGBA8 storage;
+    /// use `map_region` to rewrite pixels of an indexed canvas in place.
+    ///
+    /// # Panics
+    /// If the canvas uses `CanvasPixelFormat::Indexed8`.
+    pub fn pixels_mut(&mut self, start: UVec2, size: UVec2) -> impl Iterator<Item = (UVec2, &mut u32)> + '_ {
+        self.rows_mut(start, size)
+            .flat_map(|(y, xs, pixels)| xs.zip(pixels.iter_mut()).map(move |(x, px)| (UVec2::new(x, y), px)))
+    }
+
+    /// Parallel variant of `map_region`, processing each overlapped chunk on the `ComputeTaskPool`.
+    ///
+    /// Visit order is unspecified, so `f` must not depend on it.
+    pub fn par_map_region<F>(&mut self, start: UVec2, size: UVec2, f: F)
+    where
+        F: Fn(UVec2, u32) -> u32 + Send + Sync,
+    {
+        let layout = self.layout();
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        // The wrapped rect is at most two ranges per axis
+        let x_ranges = wrap_range(start.x, size.x, layout.canvas_size.x);
+        let y_ranges = wrap_range(start.y, size.y, layout.canvas_size.y);
+
+        let chunk_size = layout.chunk_size;
+        let per_block = self.canvas.cpu.chunks_per_block();
+        let f = &f;
+
+        let canvas = &mut **self.canvas;
+        let blocks = canvas
+            .cpu
+            .blocks_mut()
+            .into_iter()
+            .zip(canvas.dirty.rects_mut().chunks_mut(per_block));
+        ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+            for (mut block, rects) in blocks {
+                let block_min = block.origin;
+                let block_max = block_min + block.size;
+
+                // Intersect the wrapped rect ranges with this block
+                let xs = x_ranges.clone().map(|r| r.start.max(block_min.x)..r.end.min(block_max.x));
+                let ys = y_ranges.clone().map(|r| r.start.max(block_min.y)..r.end.min(block_max.y));
+                if xs.iter().all(|r| r.is_empty()) || ys.iter().all(|r| r.is_empty()) {
+                    continue;
+                }
+
+                scope.spawn(async move {
+                    for ys in ys.iter().filter(|r| !r.is_empty()) {
+                        for xs in xs.iter().filter(|r| !r.is_empty()) {
+                            let local_min = UVec2::new(xs.start, ys.start) - block_min;
+                            let width = xs.end - xs.start;
+                            for y in ys.clone() {
+                                let local_y = y - block_min.y;
+                                let index = local_y as usize * block.stride + local_min.x as usize;
+                                block
+                                    .pixels
+                                    .map_run(index, width as usize, |i, old| f(UVec2::new(xs.start + i as u32, y), old));
+                            }
+                            let size = UVec2::new(width, ys.end - ys.start);
+                            mark_chunk_rects(rects, per_block as u32, chunk_size, local_min, size);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    /// The whole canvas as one mutable row-major slice with its stride (the canvas width).
+    ///
+    /// Writes through the slice are not dirty-tracked: call `mark_dirty` for every region changed,
+    /// or it will not be uploaded. They also bypass `DrawOptions` and the quantiser.
+    /// Returns `None` unless the canvas uses `CanvasStorage::Contiguous` with `CanvasPixelFormat::Rgba8`.
+    #[inline]
+    pub fn contiguous_mut(&mut self) -> Option<(&mut [u32], usize)> {
+        let stride = self.canvas.cpu.row_stride(0);
+        self.canvas.cpu.contiguous_mut().map(|pixels| (pixels, stride))
+    }
+
+    /// Mark a canvas rectangle dirty so it is uploaded with the next frame, wrapping toroidally.
+    pub fn mark_dirty(&mut self, start: UVec2, size: UVec2) {
+        let layout = self.layout();
+        let size = size.min(layout.canvas_size);
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        for ys in wrap_range(start.y, size.y, layout.canvas_size.y) {
+            for xs in wrap_range(start.x, size.x, layout.canvas_size.x) {
+                self.canvas
+                    .dirty
+                    .mark_canvas_rect(UVec2::new(xs.start, ys.start), UVec2::new(xs.len() as u32, ys.len() as u32));
+            }
+        }
+    }
+
+    #[inline]
+    fn layout(&self) -> CanvasLayout {
+        self.canvas.config.layout()
+    }
+
+    /// Writer over the canvas buffers.
+    #[inline]
+    fn writer(&mut self) -> CanvasWriter<'_> {
+        self.canvas.writer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::config::{CanvasChunking, CanvasConfig, CanvasStorage};
+
+    /// A 10x6 canvas of ragged 4x4 chunks (3x2, the last column and row short).
+    fn world(storage: CanvasStorage) -> World {
+        let mut world = World::new();
+        world.spawn((
+            Canvas::new(CanvasConfig {
+                canvas_size: UVec2::new(10, 6),
+                chunking: CanvasChunking::Size(UVec2::splat(4)),
+                clear_colour: 0,
+                storage,
+                ..default()
+            }),
+            PrimaryCanvas,
+        ));
+        world
+    }
+
+    /// Distinct value for every canvas pixel.
+    fn value(pos: UVec2) -> u32 {
+        pos.y * 100 + pos.x + 1
+    }
+
+    /// `world` with every pixel set to `value`.
+    fn numbered(storage: CanvasStorage) -> World {
+        let mut world = world(storage);
+        let mut canvas = world.query::<&mut Canvas>().single_mut(&mut world).unwrap();
+        for y in 0..6 {
+            for x in 0..10 {
+                let (buffer_index, index) = canvas.cpu.locate(UVec2::new(x, y));
+                canvas
+                    .cpu
+                    .buffer_mut(buffer_index)
+                    .map_run(index, 1, |_, _| value(UVec2::new(x, y)));
+            }
+        }
+        world
+    }
+
+    /// The pixels of a rectangle, wrapped onto the 10x6 canvas, in row-major order.
+    fn expected_rect(start: UVec2, size: UVec2) -> Vec<u32> {
+        (0..size.y)
+            .flat_map(|dy| (0..size.x).map(move |dx| value(UVec2::new((start.x + dx) % 10, (start.y + dy) % 6))))
+            .collect()
+    }
+
+    /// Take every chunk's dirty rect, in chunk index order.
+    fn take_dirty(world: &mut World) -> Vec<Option<(UVec2, UVec2)>> {
+        let mut canvas = world.query::<&mut Canvas>().single_mut(world).unwrap();
+        (0..canvas.dirty.len()).map(|i| canvas.dirty.take(i)).collect()
+    }
+
+    /// The whole canvas, row-major.
+    fn read_all(world: &mut World) -> Vec<u32> {
+        world
+            .run_system_once(|reader: CanvasReader| reader.read_rect(UVec2::ZERO, reader.canvas_size()))
+            .unwrap()
+    }
+
+    #[test]
+    fn read_rect_crosses_chunk_seams_and_the_wrap_edge() {
+        let cases = [
+            // Across the x = 4 and y = 4 chunk seams
+            (UVec2::new(2, 1), UVec2::new(5, 4)),
+            // Across the right and bottom canvas edges, wrapping into the first chunk
+            (UVec2::new(8, 4), UVec2::new(5, 4)),
+            // Starting outside the canvas
+            (UVec2::new(13, 7), UVec2::new(3, 2)),
+        ];
+        for storage in [CanvasStorage::Chunked, CanvasStorage::Contiguous] {
+            let mut world = numbered(storage);
+            for (start, size) in cases {
+                let (pixels, into) = world
+                    .run_system_once(move |reader: CanvasReader| {
+                        let mut into = vec![0; (size.x * size.y) as usize];
+                        reader.read_rect_into(start, size, &mut into);
+                        (reader.read_rect(start, size), into)
+                    })
+                    .unwrap();
+                let expected = expected_rect(start, size);
+                assert_eq!(pixels, expected, "{storage:?} {start} {size}");
+                assert_eq!(into, expected, "{storage:?} {start} {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn neighbourhoods_wrap_at_edges_and_corners() {
+        let at = |pos: UVec2, d: IVec2| {
+            let x = (pos.x as i32 + d.x).rem_euclid(10) as u32;
+            let y = (pos.y as i32 + d.y).rem_euclid(6) as u32;
+            value(UVec2::new(x, y))
+        };
+
+        for storage in [CanvasStorage::Chunked, CanvasStorage::Contiguous] {
+            let mut world = numbered(storage);
+            world
+                .run_system_once(move |reader: CanvasReader| {
+                    // Every pixel, so both the interior fast path and the wrapped slow path are exercised
+                    for y in 0..6 {
+                        for x in 0..10 {
+                            let pos = UVec2::new(x, y);
+                            assert_eq!(reader.moore(pos), MOORE.map(|d| at(pos, d)), "{storage:?} {pos}");
+                            assert_eq!(reader.von_neumann(pos), VON_NEUMANN.map(|d| at(pos, d)), "{storage:?} {pos}");
+                            for radius in [1, 2] {
+                                let start = UVec2::new((x + 10 - radius) % 10, (y + 6 - radius) % 6);
+                                let side = 2 * radius + 1;
+                                assert_eq!(
+                                    reader.window(pos, radius),
+                                    expected_rect(start, UVec2::splat(side)),
+                                    "{storage:?} {pos} radius {radius}"
+                                );
+                            }
+                        }
+                    }
+
+                    // Positions outside the canvas wrap first
+                    assert_eq!(reader.moore(UVec2::new(10, 6)), reader.moore(UVec2::ZERO), "{storage:?}");
+                })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn rows_mut_splits_ragged_chunks() {
+        for storage in [CanvasStorage::Chunked, CanvasStorage::Contiguous] {
+            let mut world = world(storage);
+            let (start, size) = (UVec2::new(2, 1), UVec2::new(7, 4));
+
+            let rows = world
+                .run_system_once(move |mut painter: CanvasPainter| {
+                    let mut rows = Vec::new();
+                    for (y, xs, pixels) in painter.rows_mut(start, size) {
+                        assert_eq!(pixels.len(), xs.len());
+                        for (x, px) in xs.clone().zip(pixels.iter_mut()) {
+                            *px += x * 100 + y + 1;
+                        }
+                        rows.push((y, xs));
+                    }
+                    rows
+                })
+                .unwrap();
+
+            // Runs never cross a chunk edge
+            for (y, xs) in &rows {
+                assert_eq!(xs.start / 4, (xs.end - 1) / 4, "{storage:?} row {y} {xs:?}");
+            }
+            assert_eq!(rows.len(), 12, "{storage:?}");
+
+            let pixels = world
+                .run_system_once(|reader: CanvasReader| reader.read_rect(UVec2::ZERO, reader.canvas_size()))
+                .unwrap();
+            for y in 0..6 {
+                for x in 0..10 {
+                    let inside = (start.x..start.x + size.x).contains(&x) && (start.y..start.y + size.y).contains(&y);
+                    let expected = if inside { x * 100 + y + 1 } else { 0 };
+                    assert_eq!(pixels[(y * 10 + x) as usize], expected, "{storage:?} ({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rows_mut_limits_size_to_the_canvas() {
+        let mut world = world(CanvasStorage::Chunked);
+        let count = world
+            .run_system_once(|mut painter: CanvasPainter| {
+                painter
+                    .rows_mut(UVec2::new(8, 4), UVec2::new(20, 20))
+                    .map(|(_, xs, _)| xs.len())
+                    .sum::<usize>()
+            })
+            .unwrap();
+        assert_eq!(count, 60);
+    }
+
+    #[test]
+    fn par_for_each_chunk_visits_every_pixel_once() {
+        for storage in [CanvasStorage::Chunked, CanvasStorage::Contiguous] {
+            let mut world = numbered(storage);
+            let mut chunks = world
+                .run_system_once(|mut painter: CanvasPainter| {
+                    let chunks = std::sync::Mutex::new(Vec::new());
+                    painter.par_for_each_chunk(|chunk_xy, pixels, dirty| {
+                        let size = dirty.chunk_size();
+                        assert_eq!(pixels.len(), (size.x * size.y) as usize);
+                        for (i, px) in pixels.iter_mut().enumerate() {
+                            let pos = dirty.origin() + UVec2::new(i as u32 % size.x, i as u32 / size.x);
+                            // A pixel seen twice, or at the wrong position, is poisoned
+                            *px = if *px == value(pos) { *px + 1000 } else { u32::MAX };
+                        }
+                        if chunk_xy == UVec2::new(1, 1) {
+                            dirty.mark_rect(UVec2::new(1, 0), UVec2::new(2, 1));
+                        }
+                        chunks.lock().unwrap().push((chunk_xy, dirty.origin(), size));
+                    });
+                    chunks.into_inner().unwrap()
+                })
+                .unwrap();
+
+            chunks.sort_by_key(|(xy, ..)| (xy.y, xy.x));
+            let expected: Vec<_> = (0..2)
+                .flat_map(|y| (0..3).map(move |x| UVec2::new(x, y)))
+                .map(|xy| (xy, xy * 4, (UVec2::new(10, 6) - xy * 4).min(UVec2::splat(4))))
+                .collect();
+            assert_eq!(chunks, expected, "{storage:?}");
+
+            assert_eq!(
+                read_all(&mut world),
+                expected_rect(UVec2::ZERO, UVec2::new(10, 6))
+                    .iter()
+                    .map(|v| v + 1000)
+                    .collect::<Vec<_>>(),
+                "{storage:?}"
+            );
+
+            // Only the region the closure marked is dirty
+            let mut dirty = vec![None; 6];
+            dirty[4] = Some((UVec2::new(1, 0), UVec2::new(2, 0)));
+            assert_eq!(take_dirty(&mut world), dirty, "{storage:?}");
+        }
+    }
+
+    #[test]
+    fn map_region_visits_every_pixel_once() {
+        let (start, size) = (UVec2::new(8, 4), UVec2::new(5, 4));
+        let inside = |pos: UVec2| (pos.x + 10 - start.x) % 10 < size.x && (pos.y + 6 - start.y) % 6 < size.y;
+
+        for storage in [CanvasStorage::Chunked, CanvasStorage::Contiguous] {
+            for parallel in [false, true] {
+                let mut world = numbered(storage);
+                world
+                    .run_system_once(move |mut painter: CanvasPainter| {
+                        // A pixel seen twice, or at the wrong position, is poisoned
+                        let f = |pos, old| if old == value(pos) { old + 1000 } else { u32::MAX };
+                        if parallel {
+                            painter.par_map_region(start, size, f);
+                        } else {
+                            painter.map_region(start, size, f);
+                        }
+                    })
+                    .unwrap();
+
+                let pixels = read_all(&mut world);
+                for (i, px) in pixels.into_iter().enumerate() {
+                    let pos = UVec2::new(i as u32 % 10, i as u32 / 10);
+                    let expected = value(pos) + if inside(pos) { 1000 } else { 0 };
+                    assert_eq!(px, expected, "{storage:?} parallel {parallel} {pos}");
+                }
+
+                // The wrapped rect covers columns 8..10 and 0..3, rows 4..6 and 0..2
+                let dirty = take_dirty(&mut world);
+                let expected = [
+                    Some((UVec2::ZERO, UVec2::new(2, 1))),
+                    None,
+                    Some((UVec2::ZERO, UVec2::new(1, 1))),
+                    Some((UVec2::ZERO, UVec2::new(2, 1))),
+                    None,
+                    Some((UVec2::ZERO, UVec2::new(1, 1))),
+                ];
+                assert_eq!(dirty, expected, "{storage:?} parallel {parallel}");
+            }
+        }
+    }
+
+    #[test]
+    fn map_region_limits_size_to_the_canvas() {
+        let mut world = numbered(CanvasStorage::Contiguous);
+        world
+            .run_system_once(|mut painter: CanvasPainter| {
+                painter.map_region(UVec2::new(3, 2), UVec2::new(25, 25), |pos, old| {
+                    if old == value(pos) { old + 1000 } else { u32::MAX }
+                });
+            })
+            .unwrap();
+        let expected: Vec<_> = expected_rect(UVec2::ZERO, UVec2::new(10, 6))
+            .iter()
+            .map(|v| v + 1000)
+            .collect();
+        assert_eq!(read_all(&mut world), expected);
+        assert!(take_dirty(&mut world).iter().all(Option::is_some));
+    }
+}
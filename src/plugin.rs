@@ -1,41 +1,96 @@
+use core::marker::PhantomData;
+
 use bevy::{
     prelude::*,
-    render::{Render, RenderApp, RenderSystems, extract_resource::ExtractResourcePlugin},
+    render::{ExtractSchedule, Render, RenderApp, RenderSystems, renderer::RenderDevice},
 };
 
 use super::{
+    components::{Canvas, PrimaryCanvas},
     config::CanvasConfig,
-    messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawSpan},
-    resources::{CanvasImageHandles, CanvasUploadOps},
-    systems::{apply_canvas_uploads, collect_ops, spawn_canvas},
+    messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawShape, DrawSpan, ResizeCanvas},
+    resources::{CanvasPalette, CanvasUploadOps, SimGrid},
+    systems::{
+        apply_canvas_uploads, collect_ops, extract_canvas_uploads, init_canvases, prepare_uploads, render_sim_grid,
+        resize_canvas,
+    },
 };
 
-/// Plugin for a chunked 2D canvas.
+/// Plugin for chunked 2D canvases.
+///
+/// Spawns the `PrimaryCanvas` from `config`; further canvases can be spawned as `Canvas` entities.
+///
+/// # Panics
+/// If `config` is invalid (see `CanvasConfig::validate`).
 pub struct CanvasPlugin {
     pub config: CanvasConfig,
 }
 
 impl Plugin for CanvasPlugin {
     fn build(&self, app: &mut App) {
-        // Plugins.
-        app.add_plugins(ExtractResourcePlugin::<CanvasUploadOps>::default());
+        if let Err(error) = self.config.validate() {
+            panic!("CanvasPlugin: invalid CanvasConfig: {error}");
+        }
 
         // Messages
         app.add_message::<ClearCanvas>()
             .add_message::<DrawPixel>()
             .add_message::<DrawPixels>()
             .add_message::<DrawRect>()
-            .add_message::<DrawSpan>();
+            .add_message::<DrawSpan>()
+            .add_message::<DrawShape>()
+            .add_message::<ResizeCanvas>();
 
         // Resources
-        app.insert_resource(self.config.clone());
+        app.init_resource::<CanvasPalette>().init_resource::<CanvasUploadOps>();
+
+        // Primary canvas
+        app.world_mut().spawn((Canvas::new(self.config.clone()), PrimaryCanvas));
 
         // Systems
-        app.add_systems(Startup, spawn_canvas)
-            .add_systems(Update, collect_ops.run_if(resource_exists::<CanvasImageHandles>));
+        app.add_systems(Update, (resize_canvas, collect_ops).chain())
+            .add_systems(PostUpdate, (init_canvases, prepare_uploads).chain());
 
-        // Render-world system
+        // Render-world systems
         app.sub_app_mut(RenderApp)
+            .init_resource::<CanvasUploadOps>()
+            .add_systems(ExtractSchedule, extract_canvas_uploads)
             .add_systems(Render, apply_canvas_uploads.in_set(RenderSystems::Queue));
     }
+
+    fn finish(&self, app: &mut App) {
+        // The renderer is ready now, so check the chunk size against the device's actual texture limit
+        let Some(device) = app.world().get_resource::<RenderDevice>() else {
+            return;
+        };
+        let max_texture_dimension = device.limits().max_texture_dimension_2d;
+        if let Err(error) = self.config.validate_with_limit(max_texture_dimension) {
+            panic!("CanvasPlugin: invalid CanvasConfig for this GPU: {error}");
+        }
+    }
+}
+
+/// Plugin that renders a `SimGrid<T>` resource into the canvas each frame.
+///
+/// Add it alongside `CanvasPlugin` and insert the `SimGrid<T>` resource; until then it does nothing.
+/// The grid is rendered into the `PrimaryCanvas`.
+pub struct SimGridPlugin<T> {
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for SimGridPlugin<T> {
+    fn default() -> Self {
+        Self { _marker: PhantomData }
+    }
+}
+
+impl<T: PartialEq + Send + Sync + 'static> Plugin for SimGridPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            render_sim_grid::<T>
+                .before(prepare_uploads)
+                .run_if(resource_exists::<SimGrid<T>>),
+        );
+    }
 }
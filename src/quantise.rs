@@ -0,0 +1,156 @@
+//! Write-stage colour quantisation.
+//!
+//! When configured, every pixel written to an RGBA8 canvas is mapped to the nearest entry of a fixed palette,
+//! optionally with ordered dithering keyed on canvas coordinates so patterns stay stable under redraws.
+
+/// Ordered dithering pattern applied before quantisation.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Dither {
+    /// No dithering; plain nearest-colour mapping.
+    #[default]
+    None,
+    /// 2x2 Bayer matrix.
+    Bayer2,
+    /// 4x4 Bayer matrix.
+    Bayer4,
+    /// 8x8 Bayer matrix.
+    Bayer8,
+}
+
+impl Dither {
+    /// Matrix order as a power of two, or zero for no dithering.
+    #[inline]
+    fn bits(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Bayer2 => 1,
+            Self::Bayer4 => 2,
+            Self::Bayer8 => 3,
+        }
+    }
+}
+
+/// Maps incoming colours to the nearest entry of a fixed palette.
+#[derive(Clone, Debug)]
+pub struct CanvasQuantiser {
+    /// Packed RGBA8 palette colours.
+    pub palette: Vec<u32>,
+    /// Ordered dithering pattern.
+    pub dither: Dither,
+    /// Dither amplitude in 8-bit channel units.
+    /// Roughly the distance between neighbouring palette levels works well.
+    pub spread: f32,
+}
+
+impl CanvasQuantiser {
+    /// Create a quantiser with the default dither spread.
+    ///
+    /// `palette` should not be empty; `CanvasConfig::validate` rejects a quantiser without colours.
+    pub fn new(palette: Vec<u32>, dither: Dither) -> Self {
+        Self {
+            palette,
+            dither,
+            spread: 32.0,
+        }
+    }
+
+    /// Quantise a run of pixels in place, starting at canvas position (`x`, `y`) and advancing in X.
+    #[inline]
+    pub fn quantise_run(&self, x: u32, y: u32, pixels: &mut [u32]) {
+        for (i, px) in pixels.iter_mut().enumerate() {
+            *px = self.quantise(x + i as u32, y, *px);
+        }
+    }
+
+    /// Quantise one pixel at canvas position (`x`, `y`).
+    ///
+    /// Returns `rgba_u32` unchanged if the palette is empty.
+    #[inline]
+    pub fn quantise(&self, x: u32, y: u32, rgba_u32: u32) -> u32 {
+        let Some(&first) = self.palette.first() else {
+            return rgba_u32;
+        };
+
+        let offset = self.threshold(x, y) * self.spread;
+        let [r, g, b, a] = rgba_u32.to_le_bytes();
+        let target = [r as f32 + offset, g as f32 + offset, b as f32 + offset, a as f32];
+
+        let mut best = first;
+        let mut best_distance = f32::MAX;
+        for &colour in &self.palette {
+            let [pr, pg, pb, pa] = colour.to_le_bytes();
+            let distance = [pr, pg, pb, pa]
+                .iter()
+                .zip(target)
+                .map(|(&p, t)| (p as f32 - t) * (p as f32 - t))
+                .sum::<f32>();
+            if distance < best_distance {
+                best_distance = distance;
+                best = colour;
+            }
+        }
+        best
+    }
+
+    /// Ordered dither threshold in `[-0.5, 0.5)` for a canvas position.
+    #[inline]
+    fn threshold(&self, x: u32, y: u32) -> f32 {
+        let bits = self.dither.bits();
+        if bits == 0 {
+            return 0.0;
+        }
+
+        // Bayer index by bit-interleaving (x ^ y) and y, most significant level last
+        let xy = x ^ y;
+        let mut index = 0;
+        for bit in 0..bits {
+            index = (index << 2) | (((xy >> bit) & 1) << 1) | ((y >> bit) & 1);
+        }
+
+        let cells = (1u32 << (2 * bits)) as f32;
+        (index as f32 + 0.5) / cells - 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bayer_thresholds_cover_each_level_once_per_tile() {
+        for (dither, n) in [(Dither::Bayer2, 2), (Dither::Bayer4, 4), (Dither::Bayer8, 8)] {
+            let quantiser = CanvasQuantiser::new(vec![0], dither);
+            let cells = (n * n) as f32;
+
+            let mut levels: Vec<u32> = (0..n)
+                .flat_map(|y| (0..n).map(move |x| (x, y)))
+                .map(|(x, y)| ((quantiser.threshold(x, y) + 0.5) * cells - 0.5).round() as u32)
+                .collect();
+            levels.sort_unstable();
+            assert_eq!(levels, (0..n * n).collect::<Vec<_>>(), "{dither:?}");
+
+            // The pattern tiles the canvas
+            assert_eq!(quantiser.threshold(1, 2), quantiser.threshold(1 + n, 2 + 3 * n), "{dither:?}");
+        }
+    }
+
+    #[test]
+    fn bayer2_matrix() {
+        let quantiser = CanvasQuantiser::new(vec![0], Dither::Bayer2);
+        let index = |x, y| ((quantiser.threshold(x, y) + 0.5) * 4.0 - 0.5).round() as u32;
+        assert_eq!([index(0, 0), index(1, 0), index(0, 1), index(1, 1)], [0, 2, 3, 1]);
+        assert_eq!(CanvasQuantiser::new(vec![0], Dither::None).threshold(3, 5), 0.0);
+    }
+
+    #[test]
+    fn quantise_picks_the_nearest_colour() {
+        let black = u32::from_le_bytes([0, 0, 0, 255]);
+        let white = u32::from_le_bytes([255, 255, 255, 255]);
+        let quantiser = CanvasQuantiser::new(vec![black, white], Dither::None);
+        assert_eq!(quantiser.quantise(0, 0, u32::from_le_bytes([100, 100, 100, 255])), black);
+        assert_eq!(quantiser.quantise(0, 0, u32::from_le_bytes([200, 180, 160, 255])), white);
+
+        let empty = CanvasQuantiser::new(Vec::new(), Dither::Bayer4);
+        assert_eq!(empty.quantise(2, 3, 0x1234_5678), 0x1234_5678);
+    }
+}
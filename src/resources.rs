@@ -1,19 +1,27 @@
-//! Resources used by the canvas.
+//! Resources used by the canvas, and the per-canvas state owned by `Canvas`.
 
-use bevy::{math::U8Vec2, prelude::*, render::extract_resource::ExtractResource};
+use bevy::{
+    prelude::*,
+    tasks::{ComputeTaskPool, TaskPool},
+};
 
-use super::types::{CanvasUploadOp, DirtyRect};
+use super::{
+    config::{CanvasPixelFormat, CanvasStorage},
+    options::DrawOptions,
+    types::{self, CanvasLayout, CanvasUploadOp, ChunkPixels, DirtyRect, MOORE, PixelSliceMut, StorageBlock, VON_NEUMANN},
+    utils,
+};
 
 /// Stores the `Image` handles for each chunk.
 /// This allows upload ops to reference the correct GPU `Image`.
-#[derive(Resource, Default)]
+#[derive(Default)]
 pub struct CanvasImageHandles {
     handles: Vec<Handle<Image>>,
 }
 
 impl CanvasImageHandles {
     /// Create chunk handle storage.
-    pub fn new(num_chunks: U8Vec2, handles: Vec<Handle<Image>>) -> Self {
+    pub fn new(num_chunks: UVec2, handles: Vec<Handle<Image>>) -> Self {
         debug_assert!(num_chunks.x > 0);
         debug_assert!(num_chunks.y > 0);
 
@@ -28,85 +36,249 @@ impl CanvasImageHandles {
     pub fn handle(&self, index: usize) -> &Handle<Image> {
         &self.handles[index]
     }
+
+    /// All handles, in row-major chunk ordering.
+    #[inline]
+    pub fn handles(&self) -> &[Handle<Image>] {
+        &self.handles
+    }
+
+    /// Whether the chunk images have not been created yet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
 }
 
-/// CPU backing store: row-major `u32` pixels per chunk.
-#[derive(Resource)]
+/// CPU backing store: row-major pixels, either one buffer per chunk or one canvas-sized buffer.
 pub struct CanvasCpuChunks {
-    num_chunks: U8Vec2,
-    chunk_size: UVec2,
-    chunk_data: Vec<Vec<u32>>,
+    layout: CanvasLayout,
+    storage: CanvasStorage,
+    buffers: Vec<ChunkPixels>,
 }
 
 impl CanvasCpuChunks {
-    /// Create CPU chunks, filling all pixels with `default_colour`.
-    pub fn new(num_chunks: U8Vec2, chunk_size: UVec2, default_colour: u32) -> Self {
-        debug_assert!(num_chunks.x > 0);
-        debug_assert!(num_chunks.y > 0);
-        debug_assert!(chunk_size.x > 0);
-        debug_assert!(chunk_size.y > 0);
-
+    /// Create CPU storage for `layout` in the given format, filling all pixels with `default_colour`.
+    pub fn new(layout: CanvasLayout, format: CanvasPixelFormat, storage: CanvasStorage, default_colour: u32) -> Self {
+        let num_chunks = layout.num_chunks();
         let total_chunks = (num_chunks.x as usize) * (num_chunks.y as usize);
-        let pixels_per_chunk = (chunk_size.x as usize) * (chunk_size.y as usize);
 
-        let mut chunk_data = Vec::with_capacity(total_chunks);
-        for _ in 0..total_chunks {
-            chunk_data.push(vec![default_colour; pixels_per_chunk]);
-        }
+        // Each chunk buffer is sized to its own extent, so ragged edge chunks are smaller
+        let buffers = match storage {
+            CanvasStorage::Chunked => (0..total_chunks)
+                .map(|index| {
+                    let extent = layout.chunk_extent(layout.chunk_xy_of(index));
+                    ChunkPixels::new(format, (extent.x as usize) * (extent.y as usize), default_colour)
+                })
+                .collect(),
+            CanvasStorage::Contiguous => {
+                vec![ChunkPixels::new(
+                    format,
+                    (layout.canvas_size.x as usize) * (layout.canvas_size.y as usize),
+                    default_colour,
+                )]
+            }
+        };
 
         Self {
-            num_chunks,
-            chunk_size,
-            chunk_data,
+            layout,
+            storage,
+            buffers,
         }
     }
 
-    /// Chunk row stride in pixels.
+    /// Canvas geometry of this store.
     #[inline]
-    pub fn stride(&self) -> usize {
-        self.chunk_size.x as usize
+    pub fn layout(&self) -> CanvasLayout {
+        self.layout
     }
 
-    /// Convert chunk position key (x,y) to a linear index.
+    /// Copy a rect of pixels from another store, which may have a different size, chunking or layout.
+    ///
+    /// Both rects must lie inside their canvases; values are copied verbatim.
+    pub fn copy_rect_from(&mut self, src: &CanvasCpuChunks, src_min: UVec2, dst_min: UVec2, size: UVec2) {
+        let (src_layout, dst_layout) = (src.layout(), self.layout());
+        let mut row = vec![0u32; size.x as usize];
+
+        for dy in 0..size.y {
+            let mut col = 0;
+            for (pos, run) in src_layout.rect_runs(src_min + UVec2::new(0, dy), UVec2::new(size.x, 1)) {
+                let (buffer_index, index) = src.locate(pos);
+                let run = run as usize;
+                src.buffer(buffer_index).read(index, &mut row[col..col + run]);
+                col += run;
+            }
+
+            let mut col = 0;
+            for (pos, run) in dst_layout.rect_runs(dst_min + UVec2::new(0, dy), UVec2::new(size.x, 1)) {
+                let (buffer_index, index) = self.locate(pos);
+                let run = run as usize;
+                self.buffer_mut(buffer_index)
+                    .write(index, &row[col..col + run], &DrawOptions::default());
+                col += run;
+            }
+        }
+    }
+
+    /// Row stride in pixels of a buffer (its chunk's width, or the canvas width when contiguous).
     #[inline]
-    fn index(&self, chunk_key: &U8Vec2) -> usize {
-        chunk_key.y as usize * self.num_chunks.x as usize + chunk_key.x as usize
+    pub fn row_stride(&self, buffer_index: usize) -> usize {
+        self.buffer_extent(buffer_index).x as usize
     }
 
-    /// Borrow a chunk by linear index.
-    pub fn chunk(&self, index: usize) -> &[u32] {
-        debug_assert!(index < self.chunk_data.len());
-        &self.chunk_data[index]
+    /// Pixel extent covered by a buffer.
+    #[inline]
+    fn buffer_extent(&self, buffer_index: usize) -> UVec2 {
+        match self.storage {
+            CanvasStorage::Chunked => self.layout.chunk_extent(self.layout.chunk_xy_of(buffer_index)),
+            CanvasStorage::Contiguous => self.layout.canvas_size,
+        }
     }
 
-    /// Borrow a chunk mutably by position key (x,y).
+    /// Resolve a wrapped canvas position to `(buffer index, pixel offset)`.
     #[inline]
-    pub fn chunk_mut(&mut self, chunk_key: &U8Vec2) -> &mut [u32] {
-        debug_assert!(chunk_key.x < self.num_chunks.x);
-        debug_assert!(chunk_key.y < self.num_chunks.y);
-        let idx = self.index(chunk_key);
-        &mut self.chunk_data[idx]
+    pub fn locate(&self, pos: UVec2) -> (usize, usize) {
+        match self.storage {
+            CanvasStorage::Chunked => {
+                let chunk_xy = self.layout.chunk_xy(pos);
+                let local = pos - self.layout.chunk_min(chunk_xy);
+                let stride = self.layout.chunk_extent(chunk_xy).x as usize;
+                (
+                    self.layout.chunk_index(chunk_xy),
+                    local.y as usize * stride + local.x as usize,
+                )
+            }
+            CanvasStorage::Contiguous => (0, pos.y as usize * self.layout.canvas_size.x as usize + pos.x as usize),
+        }
     }
 
-    /// Mutably borrow all chunks.
+    /// Resolve a wrapped canvas position as `locate` does, but only if every pixel within `radius`
+    /// (a square window) lies in the same buffer without wrapping, so neighbours are plain offsets.
     #[inline]
-    pub fn chunks_mut(&mut self) -> &mut [Vec<u32>] {
-        &mut self.chunk_data
+    pub fn locate_interior(&self, pos: UVec2, radius: u32) -> Option<(usize, usize)> {
+        let (local, extent) = match self.storage {
+            CanvasStorage::Chunked => {
+                let chunk_xy = self.layout.chunk_xy(pos);
+                (pos - self.layout.chunk_min(chunk_xy), self.layout.chunk_extent(chunk_xy))
+            }
+            CanvasStorage::Contiguous => (pos, self.layout.canvas_size),
+        };
+        let interior = local.cmpge(UVec2::splat(radius)).all() && (local + radius).cmplt(extent).all();
+        interior.then(|| self.locate(pos))
+    }
+
+    /// Borrow a buffer by index (see `locate`).
+    #[inline]
+    pub fn buffer(&self, index: usize) -> &ChunkPixels {
+        debug_assert!(index < self.buffers.len());
+        &self.buffers[index]
+    }
+
+    /// Borrow a buffer mutably by index (see `locate`).
+    #[inline]
+    pub fn buffer_mut(&mut self, index: usize) -> &mut ChunkPixels {
+        debug_assert!(index < self.buffers.len());
+        &mut self.buffers[index]
+    }
+
+    /// Mutably borrow all buffers.
+    #[inline]
+    pub fn buffers_mut(&mut self) -> &mut [ChunkPixels] {
+        &mut self.buffers
+    }
+
+    /// The whole canvas as one row-major slice, if storage is contiguous RGBA8.
+    #[inline]
+    pub fn contiguous(&self) -> Option<&[u32]> {
+        match (self.storage, &self.buffers[0]) {
+            (CanvasStorage::Contiguous, ChunkPixels::Rgba8(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// The whole canvas as one mutable row-major slice, if storage is contiguous RGBA8.
+    #[inline]
+    pub fn contiguous_mut(&mut self) -> Option<&mut [u32]> {
+        match (self.storage, &mut self.buffers[0]) {
+            (CanvasStorage::Contiguous, ChunkPixels::Rgba8(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Number of chunks covered by each block returned from `blocks_mut`.
+    #[inline]
+    pub fn chunks_per_block(&self) -> usize {
+        match self.storage {
+            CanvasStorage::Chunked => 1,
+            CanvasStorage::Contiguous => self.layout.num_chunks().x as usize,
+        }
+    }
+
+    /// Split storage into disjoint blocks in row-major chunk order, for parallel processing.
+    ///
+    /// Chunked storage yields one block per chunk; contiguous storage yields one block per row of chunks.
+    pub fn blocks_mut(&mut self) -> Vec<StorageBlock<'_>> {
+        let layout = self.layout;
+
+        match self.storage {
+            CanvasStorage::Chunked => self
+                .buffers
+                .iter_mut()
+                .enumerate()
+                .map(|(index, buffer)| {
+                    let chunk_xy = layout.chunk_xy_of(index);
+                    let size = layout.chunk_extent(chunk_xy);
+                    StorageBlock {
+                        pixels: buffer.as_slice_mut(),
+                        stride: size.x as usize,
+                        origin: layout.chunk_min(chunk_xy),
+                        size,
+                    }
+                })
+                .collect(),
+            CanvasStorage::Contiguous => {
+                // The last band holds the ragged chunk row, which `chunks_mut` leaves short
+                let stride = layout.canvas_size.x as usize;
+                let band_len = stride * layout.chunk_size.y as usize;
+                let band = move |(row, pixels)| {
+                    let origin = layout.chunk_min(UVec2::new(0, row as u32));
+                    StorageBlock {
+                        pixels,
+                        stride,
+                        origin,
+                        size: UVec2::new(stride as u32, layout.chunk_extent(UVec2::new(0, row as u32)).y),
+                    }
+                };
+                match &mut self.buffers[0] {
+                    ChunkPixels::Rgba8(data) => data
+                        .chunks_mut(band_len)
+                        .map(PixelSliceMut::Rgba8)
+                        .enumerate()
+                        .map(band)
+                        .collect(),
+                    ChunkPixels::Indexed8(data) => data
+                        .chunks_mut(band_len)
+                        .map(PixelSliceMut::Indexed8)
+                        .enumerate()
+                        .map(band)
+                        .collect(),
+                }
+            }
+        }
     }
 }
 
 /// Dirty tracking per chunk, storing the union of all writes as an axis-aligned rect.
 /// The rect is in chunk-local coordinates (pixels), stored as inclusive min/max.
-#[derive(Resource)]
 pub struct CanvasDirtyRects {
-    num_chunks: U8Vec2,
+    num_chunks: UVec2,
     chunk_size: UVec2,
     rects: Vec<DirtyRect>,
 }
 
 impl CanvasDirtyRects {
     /// Create dirty rect tracking with all chunks initially clean.
-    pub fn new(num_chunks: U8Vec2, chunk_size: UVec2) -> Self {
+    pub fn new(num_chunks: UVec2, chunk_size: UVec2) -> Self {
         debug_assert!(num_chunks.x > 0);
         debug_assert!(num_chunks.y > 0);
         debug_assert!(chunk_size.x > 0);
@@ -123,7 +295,7 @@ impl CanvasDirtyRects {
 
     /// Convert chunk (x,y) key to a linear index.
     #[inline(always)]
-    fn index(&self, chunk_key: &U8Vec2) -> usize {
+    fn index(&self, chunk_key: &UVec2) -> usize {
         chunk_key.y as usize * self.num_chunks.x as usize + chunk_key.x as usize
     }
 
@@ -132,27 +304,28 @@ impl CanvasDirtyRects {
     /// - `min` is inclusive
     /// - `size` is extent (width/height)
     #[inline(always)]
-    pub fn mark_rect(&mut self, chunk_key: &U8Vec2, min: UVec2, size: UVec2) {
-        if size.x == 0 || size.y == 0 {
-            return;
-        }
+    pub fn mark_rect(&mut self, chunk_key: &UVec2, min: UVec2, size: UVec2) {
+        let index = self.index(chunk_key);
+        self.rects[index].mark(min, size, self.chunk_size);
+    }
 
-        // Clamp to chunk bounds defensively.
-        let max_bound = self.chunk_size - UVec2::ONE;
-        let min = min.min(max_bound);
-        let max = (min + size - UVec2::ONE).min(max_bound);
+    /// Mark every chunk clean.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.rects.fill(DirtyRect::default());
+    }
 
-        let index = self.index(chunk_key);
-        let rect = &mut self.rects[index];
+    /// Mark a rect in canvas pixel coordinates as dirty, splitting it across the chunks it overlaps.
+    /// The rect must lie inside the canvas (no wrapping).
+    #[inline]
+    pub fn mark_canvas_rect(&mut self, min: UVec2, size: UVec2) {
+        types::mark_chunk_rects(&mut self.rects, self.num_chunks.x, self.chunk_size, min, size);
+    }
 
-        if !rect.dirty {
-            rect.dirty = true;
-            rect.min = min;
-            rect.max = max;
-        } else {
-            rect.min = rect.min.min(min);
-            rect.max = rect.max.max(max);
-        }
+    /// Mutably borrow all dirty rects (row-major chunk ordering).
+    #[inline]
+    pub fn rects_mut(&mut self) -> &mut [DirtyRect] {
+        &mut self.rects
     }
 
     /// Take and clear the dirty rect for a given chunk index.
@@ -174,19 +347,453 @@ impl CanvasDirtyRects {
     }
 }
 
-/// Render-world resource holding pending canvas upload operations.
-/// This resource is cloned and extracted from the main world into the render world each frame.
-/// The render system then drains and submits the ops each frame.
-#[derive(Resource, Default, Clone)]
+/// Colour palette used to resolve `CanvasPixelFormat::Indexed8` pixels at upload time.
+///
+/// Holds up to 256 packed RGBA8 colours; unused entries resolve to transparent black.
+/// Changing an entry re-uploads every chunk that references it, which makes palette cycling cheap.
+#[derive(Resource, Clone)]
+pub struct CanvasPalette {
+    colours: [u32; 256],
+    changed: [bool; 256],
+    any_changed: bool,
+}
+
+impl CanvasPalette {
+    /// Create a palette from up to 256 packed RGBA8 colours.
+    pub fn new(colours: &[u32]) -> Self {
+        debug_assert!(colours.len() <= 256);
+
+        let mut palette = [0; 256];
+        let len = colours.len().min(256);
+        palette[..len].copy_from_slice(&colours[..len]);
+
+        Self {
+            colours: palette,
+            changed: [false; 256],
+            any_changed: false,
+        }
+    }
+
+    /// Packed RGBA8 colour for a palette index.
+    #[inline(always)]
+    pub fn get(&self, index: u8) -> u32 {
+        self.colours[index as usize]
+    }
+
+    /// Set a palette entry.
+    /// Chunks containing this index are re-uploaded on the next update.
+    #[inline]
+    pub fn set(&mut self, index: u8, colour: u32) {
+        if self.colours[index as usize] == colour {
+            return;
+        }
+        self.colours[index as usize] = colour;
+        self.changed[index as usize] = true;
+        self.any_changed = true;
+    }
+
+    /// All 256 palette entries.
+    #[inline]
+    pub fn colours(&self) -> &[u32; 256] {
+        &self.colours
+    }
+
+    /// Take and clear the set of entries changed since the last call.
+    #[inline]
+    pub(crate) fn take_changed(&mut self) -> Option<[bool; 256]> {
+        if !self.any_changed {
+            return None;
+        }
+        self.any_changed = false;
+        Some(core::mem::replace(&mut self.changed, [false; 256]))
+    }
+}
+
+impl Default for CanvasPalette {
+    /// Opaque greyscale ramp, index 0 is black and 255 is white.
+    fn default() -> Self {
+        let mut colours = [0; 256];
+        for (i, colour) in colours.iter_mut().enumerate() {
+            let v = i as u8;
+            *colour = utils::pack_rgba8([v, v, v, 255]);
+        }
+        Self::new(&colours)
+    }
+}
+
+/// Pending canvas upload operations, present in both the main and render worlds.
+///
+/// The main world fills `ops` each frame; during extraction the ops are swapped into the render world
+/// (no byte copies), and the render system drains and submits them.
+/// Spent byte buffers travel back to the main world at the next extraction and are reused.
+#[derive(Resource, Default)]
 pub struct CanvasUploadOps {
     pub ops: Vec<CanvasUploadOp>,
+    pool: Vec<Vec<u8>>,
 }
 
-impl ExtractResource for CanvasUploadOps {
-    type Source = CanvasUploadOps;
+impl CanvasUploadOps {
+    /// Clear the pending ops, returning their byte buffers to the pool.
+    #[inline]
+    pub fn recycle(&mut self) {
+        self.pool.extend(self.ops.drain(..).map(|op| op.bytes));
+    }
 
-    #[inline(always)]
-    fn extract_resource(source: &Self::Source) -> Self {
-        source.clone()
+    /// Return a spent byte buffer to the pool.
+    #[inline]
+    pub fn recycle_buffer(&mut self, bytes: Vec<u8>) {
+        self.pool.push(bytes);
+    }
+
+    /// Take an empty byte buffer with at least `capacity` bytes reserved.
+    #[inline]
+    pub fn take_buffer(&mut self, capacity: usize) -> Vec<u8> {
+        let mut bytes = self.pool.pop().unwrap_or_default();
+        bytes.clear();
+        bytes.reserve(capacity);
+        bytes
+    }
+
+    /// Move this frame's ops into `render`, and take back the render world's spent buffers.
+    ///
+    /// Any ops the render world did not consume are recycled rather than resubmitted.
+    #[inline]
+    pub fn hand_off(&mut self, render: &mut Self) {
+        render.recycle();
+        self.pool.append(&mut render.pool);
+        core::mem::swap(&mut self.ops, &mut render.ops);
+    }
+}
+
+/// Colour mapping from simulation cells to packed canvas pixels.
+type CellColour<T> = Box<dyn Fn(&T) -> u32 + Send + Sync>;
+
+/// Double-buffered simulation state rendered into the canvas by `SimGridPlugin`.
+///
+/// Cells are row-major with stride = canvas width, in canvas coordinates (as set by the canvas's `CanvasOrigin`).
+/// Steps read the front buffer and write the back buffer, then `swap` publishes the result.
+/// When exactly one swap happened since the last render, only cells that differ from the previous
+/// generation are recoloured and marked dirty; otherwise the whole grid is redrawn.
+#[derive(Resource)]
+pub struct SimGrid<T> {
+    size: UVec2,
+    front: Vec<T>,
+    back: Vec<T>,
+    colour: CellColour<T>,
+    swaps: u32,
+    redraw: bool,
+}
+
+impl<T: Clone> SimGrid<T> {
+    /// Create a grid of `size` cells (which must match the canvas size), filled with `cell`.
+    pub fn new(size: UVec2, cell: T, colour: impl Fn(&T) -> u32 + Send + Sync + 'static) -> Self {
+        debug_assert!(size.x > 0);
+        debug_assert!(size.y > 0);
+
+        let len = (size.x as usize) * (size.y as usize);
+        Self {
+            size,
+            front: vec![cell.clone(); len],
+            back: vec![cell; len],
+            colour: Box::new(colour),
+            swaps: 0,
+            redraw: true,
+        }
+    }
+}
+
+impl<T> SimGrid<T> {
+    /// Grid size in cells.
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Linear cell index for a position, wrapping toroidally.
+    #[inline]
+    pub fn index(&self, pos: UVec2) -> usize {
+        let pos = pos % self.size;
+        pos.y as usize * self.size.x as usize + pos.x as usize
+    }
+
+    /// Current generation.
+    #[inline]
+    pub fn front(&self) -> &[T] {
+        &self.front
+    }
+
+    /// Current generation as a view with toroidal neighbour lookups.
+    #[inline]
+    pub fn cells(&self) -> SimCells<'_, T> {
+        SimCells::new(self.size, &self.front)
+    }
+
+    /// Current generation, mutably. Edits made here force a full redraw.
+    #[inline]
+    pub fn front_mut(&mut self) -> &mut [T] {
+        self.redraw = true;
+        &mut self.front
+    }
+
+    /// Previous generation, or the next one while it is being built.
+    #[inline]
+    pub fn back(&self) -> &[T] {
+        &self.back
+    }
+
+    /// Next generation, being built.
+    #[inline]
+    pub fn back_mut(&mut self) -> &mut [T] {
+        self.invalidate_back();
+        &mut self.back
+    }
+
+    /// Borrow the front buffer for reading and the back buffer for writing.
+    #[inline]
+    pub fn buffers_mut(&mut self) -> (&[T], &mut [T]) {
+        self.invalidate_back();
+        (&self.front, &mut self.back)
+    }
+
+    /// Publish the back buffer as the new front buffer.
+    #[inline]
+    pub fn swap(&mut self) {
+        core::mem::swap(&mut self.front, &mut self.back);
+        self.swaps += 1;
+    }
+
+    /// Compute the next generation from the current one and swap.
+    ///
+    /// `f` receives each cell position and a view of the front buffer, and returns the new cell.
+    pub fn step(&mut self, mut f: impl FnMut(UVec2, &SimCells<T>) -> T) {
+        let width = self.size.x as usize;
+        let front = SimCells::new(self.size, &self.front);
+        for (i, cell) in self.back.iter_mut().enumerate() {
+            *cell = f(UVec2::new((i % width) as u32, (i / width) as u32), &front);
+        }
+        self.swap();
+    }
+
+    /// Force the whole grid to be redrawn on the next render (e.g. after clearing the canvas).
+    #[inline]
+    pub fn redraw(&mut self) {
+        self.redraw = true;
+    }
+
+    /// After a swap the back buffer is the rendered generation used for diffing;
+    /// once it may be overwritten, the next render has to redraw everything.
+    #[inline]
+    fn invalidate_back(&mut self) {
+        if self.swaps > 0 {
+            self.redraw = true;
+        }
+    }
+
+    /// Colour of a cell.
+    #[inline]
+    pub(crate) fn colour(&self, cell: &T) -> u32 {
+        (self.colour)(cell)
+    }
+
+    /// Take the pending render state: `None` if nothing changed, `Some(true)` if every cell must be redrawn,
+    /// or `Some(false)` if the front buffer can be diffed against the back buffer.
+    #[inline]
+    pub(crate) fn take_render(&mut self) -> Option<bool> {
+        let pending = match (self.redraw, self.swaps) {
+            (false, 0) => None,
+            (false, 1) => Some(false),
+            _ => Some(true),
+        };
+        self.redraw = false;
+        self.swaps = 0;
+        pending
+    }
+}
+
+impl<T: Send + Sync> SimGrid<T> {
+    /// Parallel variant of `step`, computing rows of the back buffer on the `ComputeTaskPool`.
+    pub fn par_step(&mut self, f: impl Fn(UVec2, &SimCells<T>) -> T + Send + Sync) {
+        let width = self.size.x as usize;
+        let front = &SimCells::new(self.size, &self.front);
+        let f = &f;
+
+        // A few bands per thread keeps tasks coarse while still balancing uneven rows
+        let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        let band_rows = (self.size.y as usize).div_ceil(pool.thread_num() * 4).max(1);
+
+        pool.scope(|scope| {
+            for (band, cells) in self.back.chunks_mut(width * band_rows).enumerate() {
+                scope.spawn(async move {
+                    let first = band * band_rows * width;
+                    for (i, cell) in cells.iter_mut().enumerate() {
+                        let i = first + i;
+                        *cell = f(UVec2::new((i % width) as u32, (i / width) as u32), front);
+                    }
+                });
+            }
+        });
+        self.swap();
+    }
+}
+
+/// Read-only view of a `SimGrid` generation, with toroidal neighbour lookups.
+pub struct SimCells<'a, T> {
+    size: UVec2,
+    cells: &'a [T],
+}
+
+impl<'a, T> SimCells<'a, T> {
+    #[inline]
+    fn new(size: UVec2, cells: &'a [T]) -> Self {
+        Self { size, cells }
+    }
+
+    /// Grid size in cells.
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// All cells, row-major with stride = grid width.
+    #[inline]
+    pub fn as_slice(&self) -> &'a [T] {
+        self.cells
+    }
+
+    /// Cell at a position, wrapping toroidally.
+    #[inline]
+    pub fn get(&self, pos: UVec2) -> &'a T {
+        let pos = pos % self.size;
+        &self.cells[pos.y as usize * self.size.x as usize + pos.x as usize]
+    }
+
+    /// Cell at `pos` offset by `delta`, wrapping toroidally.
+    #[inline]
+    pub fn neighbour(&self, pos: UVec2, delta: IVec2) -> &'a T {
+        let pos = (pos.as_i64vec2() + delta.as_i64vec2()).rem_euclid(self.size.as_i64vec2());
+        &self.cells[pos.y as usize * self.size.x as usize + pos.x as usize]
+    }
+
+    /// The 8 Moore neighbours, in the same order as `CanvasReader::moore`.
+    #[inline]
+    pub fn moore(&self, pos: UVec2) -> [&'a T; 8] {
+        MOORE.map(|delta| self.neighbour(pos, delta))
+    }
+
+    /// The 4 von Neumann neighbours, in the same order as `CanvasReader::von_neumann`.
+    #[inline]
+    pub fn von_neumann(&self, pos: UVec2) -> [&'a T; 4] {
+        VON_NEUMANN.map(|delta| self.neighbour(pos, delta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_tracks_changed_entries() {
+        let mut palette = CanvasPalette::default();
+        assert_eq!(palette.get(0), utils::pack_rgba8([0, 0, 0, 255]));
+        assert_eq!(palette.get(255), utils::pack_rgba8([255, 255, 255, 255]));
+        assert_eq!(palette.take_changed(), None);
+
+        // Setting an entry to its current colour is not a change
+        palette.set(7, palette.get(7));
+        assert_eq!(palette.take_changed(), None);
+
+        palette.set(7, 0x1234_5678);
+        palette.set(200, 0x8765_4321);
+        let changed = palette.take_changed().unwrap();
+        assert_eq!(
+            changed
+                .iter()
+                .enumerate()
+                .filter(|(_, changed)| **changed)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            vec![7, 200]
+        );
+        assert_eq!(palette.get(7), 0x1234_5678);
+        assert_eq!(palette.take_changed(), None);
+    }
+
+    fn op(bytes: Vec<u8>) -> CanvasUploadOp {
+        CanvasUploadOp {
+            handle: Handle::default(),
+            start: UVec2::ZERO,
+            size: UVec2::ONE,
+            bytes_per_row: 256,
+            bytes,
+        }
+    }
+
+    #[test]
+    fn hand_off_moves_ops_and_reuses_buffers() {
+        let mut main = CanvasUploadOps::default();
+        let mut render = CanvasUploadOps::default();
+
+        let bytes = main.take_buffer(64);
+        let ptr = bytes.as_ptr();
+        main.ops.push(op(bytes));
+        main.hand_off(&mut render);
+        assert!(main.ops.is_empty());
+        assert_eq!(render.ops.len(), 1);
+
+        // The render world spends the op and keeps its buffer for the next hand-off
+        let spent = render.ops.pop().unwrap();
+        render.recycle_buffer(spent.bytes);
+        main.hand_off(&mut render);
+        assert!(render.ops.is_empty());
+
+        let bytes = main.take_buffer(32);
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert!(bytes.is_empty());
+        assert!(bytes.capacity() >= 64);
+        main.recycle_buffer(bytes);
+
+        // Ops the render world never consumed are recycled, not resubmitted
+        let bytes = main.take_buffer(16);
+        main.ops.push(op(bytes));
+        main.hand_off(&mut render);
+        main.hand_off(&mut render);
+        assert!(main.ops.is_empty());
+        assert!(render.ops.is_empty());
+        let bytes = main.take_buffer(0);
+        assert_eq!(bytes.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn recycle_returns_op_buffers_to_the_pool() {
+        let mut ops = CanvasUploadOps::default();
+        let bytes = ops.take_buffer(8);
+        let ptr = bytes.as_ptr();
+        ops.ops.push(op(bytes));
+        ops.recycle();
+        assert!(ops.ops.is_empty());
+        let bytes = ops.take_buffer(8);
+        assert_eq!(bytes.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn sim_grid_diffs_only_after_a_single_swap() {
+        let mut grid = SimGrid::new(UVec2::new(3, 2), false, |_: &bool| 0);
+        assert_eq!(grid.take_render(), Some(true), "a new grid is drawn in full");
+        assert_eq!(grid.take_render(), None);
+
+        grid.step(|_, _| true);
+        assert_eq!(grid.take_render(), Some(false));
+
+        grid.step(|_, _| false);
+        grid.step(|_, _| true);
+        assert_eq!(grid.take_render(), Some(true), "two swaps lose the rendered generation");
+
+        grid.step(|_, _| false);
+        grid.back_mut()[0] = true;
+        assert_eq!(grid.take_render(), Some(true), "the rendered generation was overwritten");
+
+        grid.front_mut()[0] = false;
+        assert_eq!(grid.take_render(), Some(true));
+        assert_eq!(grid.take_render(), None);
     }
 }
@@ -1,6 +1,12 @@
-use bevy::math::{U8Vec2, UVec2};
+use bevy::math::{UVec2, Vec2};
+
+use super::config::CanvasAnchor;
 
 pub const DEFAULT_CLEAR_COLOUR: u32 = 0xffffffff;
 pub const DEFAULT_CANVAS_Z_INDEX: f32 = 0.0;
 pub const DEFAULT_CANVAS_SIZE: UVec2 = UVec2::new(1024, 1024);
-pub const DEFAULT_NUM_CHUNKS: U8Vec2 = U8Vec2::new(4, 4);
+pub const DEFAULT_CANVAS_ANCHOR: CanvasAnchor = CanvasAnchor::Centre;
+pub const DEFAULT_PIXEL_SCALE: Vec2 = Vec2::ONE;
+pub const DEFAULT_CHUNK_SIZE: UVec2 = UVec2::new(256, 256);
+pub const DEFAULT_MAX_TEXTURE_DIMENSION: u32 = 8192;
+pub const MAX_TOTAL_CHUNKS: usize = 1 << 20;
@@ -0,0 +1,147 @@
+//! Shape primitives with sub-pixel coordinates.
+//!
+//! Shapes are given in canvas pixel units, where pixel `p` covers `p..p + 1`.
+//! They are rasterised by pixel centres: a pixel is filled when its centre `p + 0.5` lies inside the shape.
+//! Edges are half-open (min edges inclusive, max edges exclusive), so shapes sharing an edge neither overlap nor
+//! leave a gap.
+
+use core::ops::Range;
+
+use bevy::prelude::*;
+
+/// A filled shape in canvas pixel coordinates.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CanvasShape {
+    /// Axis-aligned rectangle covering `min..max`.
+    Rect { min: Vec2, max: Vec2 },
+    /// Disc of `radius` around `centre`.
+    Circle { centre: Vec2, radius: f32 },
+}
+
+impl Default for CanvasShape {
+    fn default() -> Self {
+        Self::Rect {
+            min: Vec2::ZERO,
+            max: Vec2::ZERO,
+        }
+    }
+}
+
+impl CanvasShape {
+    /// Whether every coordinate is finite. Draws skip shapes that are not.
+    #[inline]
+    pub fn is_finite(self) -> bool {
+        match self {
+            Self::Rect { min, max } => min.is_finite() && max.is_finite(),
+            Self::Circle { centre, radius } => centre.is_finite() && radius.is_finite(),
+        }
+    }
+
+    /// Covered pixel rows, before wrap modes are applied.
+    #[inline]
+    pub(crate) fn rows(self) -> Range<i64> {
+        let (y_min, y_max) = match self {
+            Self::Rect { min, max } => (min.y, max.y),
+            Self::Circle { centre, radius } => (centre.y - radius, centre.y + radius),
+        };
+        first_pixel(y_min)..first_pixel(y_max)
+    }
+
+    /// Covered pixel columns on row `y`.
+    ///
+    /// Every row's columns lie within those of the row nearest `centre_y`.
+    #[inline]
+    pub(crate) fn columns(self, y: i64) -> Range<i64> {
+        let (x_min, x_max) = match self {
+            Self::Rect { min, max } => (min.x, max.x),
+            Self::Circle { centre, radius } => {
+                let dy = (y as f64 + 0.5 - centre.y as f64) as f32;
+                let half_width = (radius * radius - dy * dy).max(0.0).sqrt();
+                (centre.x - half_width, centre.x + half_width)
+            }
+        };
+        first_pixel(x_min)..first_pixel(x_max)
+    }
+
+    /// Vertical centre of the shape, in canvas pixel units.
+    #[inline]
+    pub(crate) fn centre_y(self) -> f64 {
+        match self {
+            Self::Rect { min, max } => (min.y as f64 + max.y as f64) / 2.0,
+            Self::Circle { centre, .. } => centre.y as f64,
+        }
+    }
+}
+
+/// First pixel whose centre is at or after `edge`.
+///
+/// Saturates to the `i32` range of draw positions, so far-off edges stay representable.
+#[inline]
+fn first_pixel(edge: f32) -> i64 {
+    (edge - 0.5).ceil() as i32 as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_edges_are_half_open() {
+        let rect = CanvasShape::Rect {
+            min: Vec2::new(0.5, 0.6),
+            max: Vec2::new(2.5, 2.5),
+        };
+        assert_eq!(rect.rows(), 1..2);
+        assert_eq!(rect.columns(1), 0..2);
+
+        let empty = CanvasShape::Rect {
+            min: Vec2::new(1.0, 1.0),
+            max: Vec2::new(1.4, 3.0),
+        };
+        assert!(empty.columns(1).is_empty());
+    }
+
+    #[test]
+    fn circle_rows_narrow_away_from_the_centre() {
+        let circle = CanvasShape::Circle {
+            centre: Vec2::new(4.0, 4.0),
+            radius: 2.0,
+        };
+        assert_eq!(circle.rows(), 2..6);
+        assert_eq!(circle.columns(2), 3..5);
+        assert_eq!(circle.columns(3), 2..6);
+        assert_eq!(circle.columns(4), 2..6);
+        assert_eq!(circle.columns(5), 3..5);
+        assert_eq!(circle.centre_y(), 4.0);
+    }
+
+    #[test]
+    fn far_edges_saturate() {
+        let rect = CanvasShape::Rect {
+            min: Vec2::splat(f32::MIN),
+            max: Vec2::splat(f32::MAX),
+        };
+        assert_eq!(rect.rows(), i32::MIN as i64..i32::MAX as i64);
+        assert!(rect.is_finite());
+
+        let circle = CanvasShape::Circle {
+            centre: Vec2::ZERO,
+            radius: f32::MAX,
+        };
+        assert_eq!(circle.columns(0), i32::MIN as i64..i32::MAX as i64);
+    }
+
+    #[test]
+    fn non_finite_shapes() {
+        let nan = CanvasShape::Rect {
+            min: Vec2::new(f32::NAN, 0.0),
+            max: Vec2::ONE,
+        };
+        let infinite = CanvasShape::Circle {
+            centre: Vec2::ZERO,
+            radius: f32::INFINITY,
+        };
+        assert!(!nan.is_finite());
+        assert!(!infinite.is_finite());
+    }
+}
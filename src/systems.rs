@@ -1,121 +1,173 @@
 use bevy::{
     asset::RenderAssetUsages,
     image::ImageSampler,
-    math::U8Vec2,
     prelude::*,
     render::{
+        MainWorld,
         render_asset::RenderAssets,
         render_resource::{
-            Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect, TextureDimension, TextureFormat,
-            TextureUsages,
+            Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect, TextureDimension, TextureUsages,
         },
-        renderer::RenderQueue,
+        renderer::{RenderDevice, RenderQueue},
         texture::GpuImage,
     },
 };
 
 use super::{
-    components::CanvasImage,
-    config::CanvasConfig,
-    messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawSpan},
-    resources::{CanvasCpuChunks, CanvasDirtyRects, CanvasImageHandles, CanvasUploadOps},
-    types::{CanvasLayout, CanvasUploadOp},
+    components::{Canvas, CanvasImage, PrimaryCanvas},
+    config::{CanvasConfig, CanvasTextureFormat},
+    messages::{ClearCanvas, DrawPixel, DrawPixels, DrawRect, DrawShape, DrawSpan, ResizeCanvas},
+    resources::{CanvasCpuChunks, CanvasDirtyRects, CanvasImageHandles, CanvasPalette, CanvasUploadOps, SimGrid},
+    types::{CanvasLayout, CanvasUploadOp, ChunkPixels},
     utils,
 };
 
-/// Spawn chunk images/sprites, and initialise CPU resources.
-pub fn spawn_canvas(mut commands: Commands, config: Res<CanvasConfig>, mut images: ResMut<Assets<Image>>) {
-    let num_chunks = config.num_chunks();
-    let chunk_size = config.chunk_size();
-    let pixels_per_chunk = config.pixels_per_chunk();
-    let clear_colour = config.clear_colour();
-
-    // Initialise GPU images with the clear colour as raw RGBA8 bytes
-    let clear_colour_bytes = utils::unpack_rgba8(clear_colour);
-    let mut data = vec![0u8; pixels_per_chunk * 4];
-    for px in data.chunks_exact_mut(4) {
-        px.copy_from_slice(&clear_colour_bytes);
-    }
-
-    // Build chunk images and spawn sprites arranged in a grid
-    let mut image_handles = Vec::with_capacity(config.total_chunks());
-    for y in 0..num_chunks.y {
-        for x in 0..num_chunks.x {
-            let mut image = Image::new(
-                Extent3d {
-                    width: chunk_size.x,
-                    height: chunk_size.y,
-                    depth_or_array_layers: 1,
-                },
-                TextureDimension::D2,
-                data.clone(),
-                TextureFormat::Rgba8UnormSrgb,
-                RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-            );
-
-            // These textures will be updated during runtime
-            image.texture_descriptor.usage |= TextureUsages::COPY_DST;
-
-            // Use nearest-neighbour sampling to avoid blurring pixel art
-            image.sampler = ImageSampler::nearest();
+/// Create the chunk images of newly spawned canvases, and spawn their sprites as children.
+/// Runs before `prepare_uploads`; the images are initialised from the CPU content, so nothing starts dirty.
+pub fn init_canvases(
+    mut commands: Commands,
+    palette: Res<CanvasPalette>,
+    mut images: ResMut<Assets<Image>>,
+    mut canvases: Query<(Entity, &mut Canvas), Added<Canvas>>,
+) {
+    for (entity, mut canvas) in &mut canvases {
+        // A canvas resized in the update it was spawned already has its images
+        if !canvas.images.is_empty() {
+            continue;
+        }
 
-            // Store image handle for later access.
-            let handle = images.add(image);
-            image_handles.push(handle.clone());
+        let canvas = &mut *canvas;
+        let image_handles = spawn_chunks(&mut commands, entity, &canvas.config, &palette, &mut images, &canvas.cpu);
+        canvas.images = CanvasImageHandles::new(canvas.config.num_chunks(), image_handles);
+        canvas.dirty.clear();
+    }
+}
 
-            // Position the chunk sprites in a grid, centred around the origin
-            commands.spawn((
-                CanvasImage,
-                Sprite::from_image(handle),
-                Transform::from_translation(Vec3::new(
-                    (x as f32 - ((num_chunks.x - 1) as f32 / 2.0)) * chunk_size.x as f32,
-                    (y as f32 - ((num_chunks.y - 1) as f32 / 2.0)) * chunk_size.y as f32,
-                    config.canvas_z_index(),
-                ))
-                // Flip Y so that canvas coords are bottom-left origin
-                .with_scale(Vec3::new(1.0, -1.0, 1.0)),
-            ));
+/// Apply the last `ResizeCanvas` message for each canvas, rebuilding its CPU buffers, chunk images and sprites.
+/// Runs before the draw messages are consumed, so draws sent alongside a resize target the new canvas.
+#[allow(clippy::too_many_arguments)]
+pub fn resize_canvas(
+    mut commands: Commands,
+    mut resize_msg: MessageReader<ResizeCanvas>,
+    palette: Res<CanvasPalette>,
+    mut images: ResMut<Assets<Image>>,
+    mut canvases: Query<&mut Canvas>,
+    primary: Option<Single<Entity, With<PrimaryCanvas>>>,
+    chunk_sprites: Query<(Entity, &ChildOf), With<CanvasImage>>,
+    render_device: Option<Res<RenderDevice>>,
+) {
+    // Keep only the last resize for each canvas
+    let primary = primary.map(|primary| *primary);
+    let mut resizes: Vec<(Entity, &ResizeCanvas)> = Vec::new();
+    for resize in resize_msg.read() {
+        let Some(target) = resize.canvas.or(primary) else {
+            continue;
+        };
+        match resizes.iter_mut().find(|(entity, _)| *entity == target) {
+            Some(entry) => entry.1 = resize,
+            None => resizes.push((target, resize)),
         }
     }
 
-    // Store image handles
-    commands.insert_resource(CanvasImageHandles::new(num_chunks, image_handles));
+    for (
+        target,
+        &ResizeCanvas {
+            size, chunking, anchor, ..
+        },
+    ) in resizes
+    {
+        let Some(mut canvas) = target_canvas(&mut canvases, Some(target), None) else {
+            continue;
+        };
+        let canvas = &mut *canvas;
 
-    // CPU chunks store packed pixels
-    commands.insert_resource(CanvasCpuChunks::new(num_chunks, chunk_size, clear_colour));
+        let config = CanvasConfig {
+            canvas_size: size,
+            chunking,
+            ..canvas.config.clone()
+        };
+        let validation = config.validate().and_then(|()| match &render_device {
+            Some(device) => config.validate_with_limit(device.limits().max_texture_dimension_2d),
+            None => Ok(()),
+        });
+        if let Err(error) = validation {
+            warn!("ResizeCanvas ignored: {error}");
+            continue;
+        }
+
+        // Carry over the overlap of the old and new canvases, placed by the anchor
+        let old_size = canvas.config.canvas_size();
+        let mut resized = Canvas::new(config);
+        let offset = anchor.offset_for_origin(canvas.config.origin(), old_size, size);
+        let src_min = (-offset).max(IVec2::ZERO).as_uvec2();
+        let dst_min = offset.max(IVec2::ZERO).as_uvec2();
+        let overlap = (old_size - src_min).min(size - dst_min);
+        resized.cpu.copy_rect_from(&canvas.cpu, src_min, dst_min, overlap);
+
+        // Replace the chunk sprites and free the old images
+        for (entity, child_of) in &chunk_sprites {
+            if child_of.parent() == target {
+                commands.entity(entity).despawn();
+            }
+        }
+        for handle in canvas.images.handles() {
+            images.remove(handle);
+        }
 
-    // Dirty rect tracking and upload ops buffer
-    commands.insert_resource(CanvasDirtyRects::new(num_chunks, chunk_size));
-    commands.insert_resource(CanvasUploadOps::default());
+        // The new images are initialised from the CPU content, so nothing starts dirty
+        let image_handles = spawn_chunks(&mut commands, target, &resized.config, &palette, &mut images, &resized.cpu);
+        resized.images = CanvasImageHandles::new(resized.config.num_chunks(), image_handles);
+        *canvas = resized;
+    }
 }
 
-/// Consume messages, write CPU buffers, compute upload ops.
+/// Consume messages and write CPU buffers.
+#[allow(clippy::too_many_arguments)]
 pub fn collect_ops(
     mut clear_canvas_msg: MessageReader<ClearCanvas>,
     mut draw_pixel_msg: MessageReader<DrawPixel>,
     mut draw_pixels_msg: MessageReader<DrawPixels>,
     mut draw_rect_msg: MessageReader<DrawRect>,
     mut draw_span_msg: MessageReader<DrawSpan>,
-    config: Res<CanvasConfig>,
-    canvas_image_handles: Res<CanvasImageHandles>,
-    mut canvas_cpu_chunks: ResMut<CanvasCpuChunks>,
-    mut canvas_dirty_rects: ResMut<CanvasDirtyRects>,
-    mut canvas_upload_ops: ResMut<CanvasUploadOps>,
+    mut draw_shape_msg: MessageReader<DrawShape>,
+    mut canvases: Query<&mut Canvas>,
+    primary: Option<Single<Entity, With<PrimaryCanvas>>>,
 ) {
-    let layout = CanvasLayout::new(config.canvas_size(), config.chunk_size());
+    let primary = primary.map(|primary| *primary);
 
     // Clear whole canvas
-    for ClearCanvas { rgba_u32 } in clear_canvas_msg.read() {
-        clear_canvas(&mut canvas_cpu_chunks, &mut canvas_dirty_rects, layout, *rgba_u32);
+    for ClearCanvas {
+        canvas,
+        rgba_u32,
+        options,
+    } in clear_canvas_msg.read()
+    {
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().clear(*rgba_u32, options);
+        }
     }
 
     // Single pixels
-    for DrawPixel { pos, rgba_u32 } in draw_pixel_msg.read() {
-        blit_pixel(&mut canvas_cpu_chunks, &mut canvas_dirty_rects, layout, *pos, *rgba_u32);
+    for DrawPixel {
+        canvas,
+        pos,
+        rgba_u32,
+        options,
+    } in draw_pixel_msg.read()
+    {
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().pixel(*pos, *rgba_u32, options);
+        }
     }
 
     // Many independent pixels
-    for DrawPixels { positions, rgba_u32 } in draw_pixels_msg.read() {
+    for DrawPixels {
+        canvas,
+        positions,
+        rgba_u32,
+        options,
+    } in draw_pixels_msg.read()
+    {
         if positions.len() != rgba_u32.len() {
             warn!(
                 "DrawPixels length mismatch (positions {}, rgba_u32 {})",
@@ -124,15 +176,24 @@ pub fn collect_ops(
             );
             continue;
         }
-        blit_pixels(&mut canvas_cpu_chunks, &mut canvas_dirty_rects, layout, positions, rgba_u32);
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().pixels(positions, rgba_u32, options);
+        }
     }
 
     // Rect writes (row-major)
-    for DrawRect { start, size, rgba_u32 } in draw_rect_msg.read() {
+    for DrawRect {
+        canvas,
+        start,
+        size,
+        rgba_u32,
+        options,
+    } in draw_rect_msg.read()
+    {
         if size.x == 0 || size.y == 0 {
             continue;
         }
-        let expected = (size.x * size.y) as usize;
+        let expected = (size.x as usize) * (size.y as usize);
         if rgba_u32.len() != expected {
             warn!(
                 "DrawRect rgba_u32 length mismatch (expected {}, got {})",
@@ -142,32 +203,150 @@ pub fn collect_ops(
             continue;
         }
 
-        blit_rect_row_major(
-            &mut canvas_cpu_chunks,
-            &mut canvas_dirty_rects,
-            layout,
-            *start,
-            *size,
-            rgba_u32,
-        );
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().rect(*start, *size, rgba_u32, options);
+        }
     }
 
     // Span writes (row-major stream)
-    for DrawSpan { start, rgba_u32 } in draw_span_msg.read() {
+    for DrawSpan {
+        canvas,
+        start,
+        rgba_u32,
+        options,
+    } in draw_span_msg.read()
+    {
         if rgba_u32.is_empty() {
             continue;
         }
-        blit_span_row_major(&mut canvas_cpu_chunks, &mut canvas_dirty_rects, layout, *start, rgba_u32);
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().span(*start, rgba_u32, options);
+        }
     }
 
-    // Convert dirty rects into upload ops for the render world.
-    build_upload_ops(
-        &canvas_image_handles,
-        &canvas_cpu_chunks,
-        &mut canvas_dirty_rects,
-        &mut canvas_upload_ops,
-        layout,
-    );
+    // Shape fills (sub-pixel coordinates)
+    for DrawShape {
+        canvas,
+        shape,
+        rgba_u32,
+        options,
+    } in draw_shape_msg.read()
+    {
+        if !shape.is_finite() {
+            warn!("DrawShape ignored: non-finite shape {shape:?}");
+            continue;
+        }
+
+        if let Some(mut canvas) = target_canvas(&mut canvases, *canvas, primary) {
+            canvas.writer().shape(*shape, *rgba_u32, options);
+        }
+    }
+}
+
+/// Convert all dirty rects written this frame (by messages or `CanvasPainter`) into upload ops, for every canvas.
+pub fn prepare_uploads(
+    mut palette: ResMut<CanvasPalette>,
+    mut canvases: Query<&mut Canvas>,
+    mut canvas_upload_ops: ResMut<CanvasUploadOps>,
+) {
+    canvas_upload_ops.recycle();
+
+    // Palette edits re-upload every chunk referencing a changed entry
+    let changed = if palette.is_changed() {
+        palette.bypass_change_detection().take_changed()
+    } else {
+        None
+    };
+
+    for mut canvas in &mut canvases {
+        let canvas = &mut *canvas;
+        if canvas.images.is_empty() {
+            continue;
+        }
+        let layout = canvas.config.layout();
+
+        if let Some(changed) = &changed {
+            mark_palette_users(&canvas.cpu, &mut canvas.dirty, layout, changed);
+        }
+
+        // Convert dirty rects into upload ops for the render world.
+        build_upload_ops(
+            &canvas.images,
+            &canvas.cpu,
+            &palette,
+            &mut canvas.dirty,
+            &mut canvas_upload_ops,
+            layout,
+            canvas.config.texture_format(),
+        );
+    }
+}
+
+/// Recolour the cells of a `SimGrid` that changed since the last render into the primary canvas.
+/// Runs before `prepare_uploads`, so the changes are uploaded the same frame.
+pub fn render_sim_grid<T: PartialEq + Send + Sync + 'static>(
+    mut grid: ResMut<SimGrid<T>>,
+    mut canvas: Single<&mut Canvas, With<PrimaryCanvas>>,
+) {
+    let canvas = &mut **canvas;
+    let canvas_size = canvas.config.canvas_size();
+    if grid.size() != canvas_size {
+        warn_once!(
+            "SimGrid size {} does not match the canvas size {}; it is not rendered",
+            grid.size(),
+            canvas_size
+        );
+        return;
+    }
+
+    let grid = grid.bypass_change_detection();
+    let Some(redraw_all) = grid.take_render() else {
+        return;
+    };
+
+    let layout = canvas.config.layout();
+    let width = canvas_size.x as usize;
+
+    for (pos, run) in layout.rect_runs(UVec2::ZERO, canvas_size) {
+        let first = pos.y as usize * width + pos.x as usize;
+        let cells = &grid.front()[first..first + run as usize];
+        let (buffer_index, index) = canvas.cpu.locate(pos);
+        let buffer = canvas.cpu.buffer_mut(buffer_index);
+        let chunk_key = layout.chunk_xy(pos);
+        let local = layout.local_xy(pos);
+
+        if redraw_all {
+            buffer.map_run(index, run as usize, |i, _| grid.colour(&cells[i]));
+            canvas.dirty.mark_rect(&chunk_key, local, UVec2::new(run, 1));
+            continue;
+        }
+
+        // The back buffer holds the previously rendered generation
+        let previous = &grid.back()[first..first + run as usize];
+        let mut changed = None;
+        buffer.map_run(index, run as usize, |i, old| {
+            if cells[i] == previous[i] {
+                return old;
+            }
+            changed = Some(changed.map_or((i, i), |(min, _)| (min, i)));
+            grid.colour(&cells[i])
+        });
+        if let Some((min, max)) = changed {
+            let min = min as u32;
+            canvas
+                .dirty
+                .mark_rect(&chunk_key, local + UVec2::new(min, 0), UVec2::new(max as u32 - min + 1, 1));
+        }
+    }
+}
+
+/// Extract-schedule system.
+/// Move this frame's upload ops into the render world, so each upload byte is produced once and consumed once.
+pub fn extract_canvas_uploads(mut main_world: ResMut<MainWorld>, mut render_uploads: ResMut<CanvasUploadOps>) {
+    let Some(mut main_uploads) = main_world.get_resource_mut::<CanvasUploadOps>() else {
+        return;
+    };
+    main_uploads.hand_off(&mut render_uploads);
 }
 
 /// Render-world system.
@@ -181,9 +360,12 @@ pub fn apply_canvas_uploads(
         return;
     }
 
-    for op in uploads.ops.drain(..) {
+    // Take the ops out so spent buffers can be returned to the pool while draining
+    let mut ops = core::mem::take(&mut uploads.ops);
+    for op in ops.drain(..) {
         // If GPU image is not ready yet, so skip
         let Some(gpu) = gpu_images.get(op.handle.id()) else {
+            uploads.recycle_buffer(op.bytes);
             continue;
         };
 
@@ -217,198 +399,81 @@ pub fn apply_canvas_uploads(
                 depth_or_array_layers: 1,
             },
         );
-    }
-}
-
-// -- Helpers --
-
-/// Clear the entire canvas to a single colour.
-/// This fills every CPU chunk and each fully dirty.
-#[inline]
-fn clear_canvas(cpu: &mut CanvasCpuChunks, dirty: &mut CanvasDirtyRects, layout: CanvasLayout, rgba_u32: u32) {
-    let num_chunks = layout.num_chunks();
 
-    // Fill every CPU chunk
-    for chunk in cpu.chunks_mut().iter_mut() {
-        chunk.fill(rgba_u32);
-    }
-
-    // Mark every chunk as fully dirty
-    for y in 0..num_chunks.y {
-        for x in 0..num_chunks.x {
-            let chunk_key = U8Vec2::new(x, y);
-            dirty.mark_rect(&chunk_key, UVec2::ZERO, layout.chunk_size);
-        }
+        // `write_texture` copies into a staging buffer, so the bytes can be reused
+        uploads.recycle_buffer(op.bytes);
     }
+    uploads.ops = ops;
 }
 
-/// Draw a single pixel.
-#[inline]
-fn blit_pixel(cpu: &mut CanvasCpuChunks, dirty: &mut CanvasDirtyRects, layout: CanvasLayout, pos: UVec2, rgba_u32: u32) {
-    write_run(cpu, dirty, layout, pos, core::slice::from_ref(&rgba_u32));
-}
-
-/// Draw many independent pixels.
-#[inline]
-fn blit_pixels(
-    cpu: &mut CanvasCpuChunks,
-    dirty: &mut CanvasDirtyRects,
-    layout: CanvasLayout,
-    positions: &[UVec2],
-    rgba_u32: &[u32],
-) {
-    debug_assert_eq!(positions.len(), rgba_u32.len());
-
-    for (pos, colour) in positions.iter().copied().zip(rgba_u32.iter().copied()) {
-        write_run(cpu, dirty, layout, pos, core::slice::from_ref(&colour));
-    }
-}
+// -- Helpers --
 
-/// Draw a row-major stream starting at `start`, wrapping at canvas edges.
+/// Resolve a message's target canvas, falling back to the primary canvas.
 #[inline]
-fn blit_span_row_major(
-    cpu: &mut CanvasCpuChunks,
-    dirty: &mut CanvasDirtyRects,
-    layout: CanvasLayout,
-    start: UVec2,
-    src_u32: &[u32],
-) {
-    if src_u32.is_empty() {
-        return;
-    }
-
-    // Cursor is always a wrapped canvas coordinate
-    let mut cursor = layout.wrap(start);
-    let mut src_index = 0;
-    let mut remaining = src_u32.len();
-
-    while remaining > 0 {
-        // Take the longest safe run at this cursor
-        let max_run = layout.max_run_len(cursor) as usize;
-        let run = remaining.min(max_run);
-        debug_assert!(run > 0);
-
-        write_run(cpu, dirty, layout, cursor, &src_u32[src_index..src_index + run]);
-
-        src_index += run;
-        remaining -= run;
-
-        // Advance cursor in row-major order across the canvas
-        cursor.x += run as u32;
-        if cursor.x == layout.canvas_size.x {
-            cursor.x = 0;
-            cursor.y += 1;
-            if cursor.y == layout.canvas_size.y {
-                cursor.y = 0;
-            }
+fn target_canvas<'a>(
+    canvases: &'a mut Query<&mut Canvas>,
+    target: Option<Entity>,
+    primary: Option<Entity>,
+) -> Option<Mut<'a, Canvas>> {
+    let Some(entity) = target.or(primary) else {
+        warn_once!("Canvas message ignored: no target canvas and no primary canvas");
+        return None;
+    };
+    match canvases.get_mut(entity) {
+        Ok(canvas) => Some(canvas),
+        Err(_) => {
+            warn!("Canvas message ignored: {entity} is not a canvas");
+            None
         }
     }
 }
 
-/// Draw a row-major rectangle, with toroidal wrap.
+/// Mark every indexed chunk that references a changed palette entry as fully dirty.
 #[inline]
-fn blit_rect_row_major(
-    cpu: &mut CanvasCpuChunks,
-    dirty: &mut CanvasDirtyRects,
-    layout: CanvasLayout,
-    start: UVec2,
-    size: UVec2,
-    src_u32: &[u32],
-) {
-    if size.x == 0 || size.y == 0 {
-        return;
-    }
-    debug_assert_eq!(src_u32.len(), (size.x * size.y) as usize);
-
-    // Source row stride is the rect width
-    let row_stride = size.x as usize;
-
-    // For each source row, write across the canvas, wrapping X as needed
-    for row in 0..size.y {
-        let y = (start.y + row) % layout.canvas_size.y;
-        let mut x = start.x % layout.canvas_size.x;
-
-        let mut src_col = 0usize;
-        let mut remaining = row_stride;
-
-        while remaining > 0 {
-            let pos = UVec2::new(x, y);
-            let max_run = layout.max_run_len(pos) as usize;
-            let run = remaining.min(max_run);
-            debug_assert!(run > 0);
-
-            let src_row_start = row as usize * row_stride;
-            let src_start = src_row_start + src_col;
-
-            write_run(cpu, dirty, layout, pos, &src_u32[src_start..src_start + run]);
-
-            src_col += run;
-            remaining -= run;
+fn mark_palette_users(cpu: &CanvasCpuChunks, dirty: &mut CanvasDirtyRects, layout: CanvasLayout, changed: &[bool; 256]) {
+    let num_chunks = layout.num_chunks();
 
-            x += run as u32;
-            if x == layout.canvas_size.x {
-                x = 0;
+    for y in 0..num_chunks.y {
+        for x in 0..num_chunks.x {
+            let chunk_key = UVec2::new(x, y);
+            let chunk_min = layout.chunk_min(chunk_key);
+            let extent = layout.chunk_extent(chunk_key);
+            let chunk_w = extent.x as usize;
+
+            // Scan the chunk row by row, which works for both storage layouts
+            let uses_changed = (0..extent.y).any(|row| {
+                let (buffer_index, start) = cpu.locate(chunk_min + UVec2::new(0, row));
+                let ChunkPixels::Indexed8(indices) = cpu.buffer(buffer_index) else {
+                    return false;
+                };
+                indices[start..start + chunk_w].iter().any(|&index| changed[index as usize])
+            });
+
+            if uses_changed {
+                dirty.mark_rect(&chunk_key, UVec2::ZERO, extent);
             }
         }
     }
 }
 
-/// Writes a contiguous run on a single scanline into chunk.
-///
-/// `src.len()` must not cross the end of the canvas row, or the end of the chunk row.
-/// This should be enforced by using `layout.max_run_len(p)` at call sites.
-#[inline]
-fn write_run(cpu: &mut CanvasCpuChunks, dirty: &mut CanvasDirtyRects, layout: CanvasLayout, dst_start: UVec2, src: &[u32]) {
-    if src.is_empty() {
-        return;
-    }
-
-    // Toroidal wrap the starting coordinate
-    let pos = layout.wrap(dst_start);
-
-    // Validate that the run is boundary-safe
-    let max_run = layout.max_run_len(pos) as usize;
-    debug_assert!(
-        src.len() <= max_run,
-        "write_run called with a run that crosses a row boundary"
-    );
-
-    // Resolve destination chunk and chunk-local coords
-    let chunk_xy = layout.chunk_xy(pos);
-    let chunk_key = layout.chunk_key(chunk_xy);
-    let local = layout.local_xy(pos);
-
-    // CPU chunk stride (pixels per row)
-    let stride = cpu.stride();
-
-    // Write into chunk-local row-major storage
-    let dst = cpu.chunk_mut(&chunk_key);
-    let dst_index = local.y as usize * stride + local.x as usize;
-
-    debug_assert!(dst_index + src.len() <= dst.len());
-    dst[dst_index..dst_index + src.len()].copy_from_slice(src);
-
-    // Dirty rect: mark the span (width = run, height = 1)
-    dirty.mark_rect(&chunk_key, local, UVec2::new(src.len() as u32, 1));
-}
-
 /// Convert per-chunk dirty rects into GPU upload ops.
+/// Indexed chunks are resolved through the palette here.
+///
+/// RGBA8 rows are cast straight into the upload bytes (or swizzled for BGRA textures),
+/// and byte buffers are reused across frames.
 ///
 /// WGPU requires `bytes_per_row` to be aligned to 256 bytes.
-/// For RGBA8, that's 4 bytes/px => 64 pixels alignment.
+/// For RGBA8, that's 4 bytes/px => 64 pixels alignment; rows cut short by a ragged chunk edge are zero-padded.
 #[inline]
 fn build_upload_ops(
     canvas_image_handles: &CanvasImageHandles,
     canvas_cpu_chunks: &CanvasCpuChunks,
+    palette: &CanvasPalette,
     canvas_dirty_rects: &mut CanvasDirtyRects,
     canvas_upload_ops: &mut CanvasUploadOps,
     layout: CanvasLayout,
+    texture_format: CanvasTextureFormat,
 ) {
-    canvas_upload_ops.ops.clear();
-
-    let chunk_w = layout.chunk_size.x;
-    let chunk_h = layout.chunk_size.y;
-
     // 256-byte alignment / 4 bytes per pixel = 64 pixels
     const ROW_ALIGN_PX: u32 = 64;
 
@@ -417,6 +482,10 @@ fn build_upload_ops(
             continue;
         };
 
+        // Edge chunks may be smaller than the nominal chunk size
+        let chunk_xy = layout.chunk_xy_of(chunk_index);
+        let UVec2 { x: chunk_w, y: chunk_h } = layout.chunk_extent(chunk_xy);
+
         // Convert inclusive max to exclusive range [min, max_ex)
         let min_ex = min;
         let max_ex = max + UVec2::ONE;
@@ -442,40 +511,450 @@ fn build_upload_ops(
             continue;
         }
 
-        let bytes_per_row = padded_width * 4;
-        debug_assert_eq!(bytes_per_row % 256, 0);
+        // Only a ragged right edge leaves the row short of the alignment
+        let bytes_per_row = (padded_width * 4).next_multiple_of(ROW_ALIGN_PX * 4);
 
         let handle = canvas_image_handles.handle(chunk_index).clone();
-        let chunk = canvas_cpu_chunks.chunk(chunk_index);
 
-        // Allocate full upload bytes once
-        let mut bytes = Vec::with_capacity((bytes_per_row as usize) * (height as usize));
-        let row_stride = chunk_w as usize;
+        // Reuse a pooled buffer sized for the whole upload
+        let mut bytes = canvas_upload_ops.take_buffer((bytes_per_row as usize) * (height as usize));
+        extend_texture_rect(
+            &mut bytes,
+            canvas_cpu_chunks,
+            palette,
+            texture_format,
+            layout.chunk_min(chunk_xy) + UVec2::new(padding_min_x, min_ex.y),
+            UVec2::new(padded_width, height),
+            bytes_per_row as usize,
+        );
 
-        // Reuseable temp row buffer to avoid many small allocations
-        let mut row_bytes = vec![0u8; (padded_width as usize) * 4];
+        canvas_upload_ops.ops.push(CanvasUploadOp {
+            handle,
+            start: UVec2::new(padding_min_x, min_ex.y),
+            size: UVec2::new(padded_width, height),
+            bytes_per_row,
+            bytes,
+        });
+    }
+}
 
-        for y in min_ex.y..max_ex.y {
-            let row_start = (y as usize) * row_stride;
+/// Create the chunk images, initialised from `cpu`, and spawn a sprite for each as a child of `canvas`.
+/// Returns the image handles in row-major chunk ordering.
+fn spawn_chunks(
+    commands: &mut Commands,
+    canvas: Entity,
+    config: &CanvasConfig,
+    palette: &CanvasPalette,
+    images: &mut Assets<Image>,
+    cpu: &CanvasCpuChunks,
+) -> Vec<Handle<Image>> {
+    let num_chunks = config.num_chunks();
+    let layout = cpu.layout();
 
-            let x0 = padding_min_x as usize;
-            let x1 = padding_max_x as usize;
+    let mut image_handles = Vec::with_capacity(config.total_chunks());
+    for y in 0..num_chunks.y {
+        for x in 0..num_chunks.x {
+            let chunk_xy = UVec2::new(x, y);
+            let chunk_min = layout.chunk_min(chunk_xy);
+            let extent = layout.chunk_extent(chunk_xy);
+
+            // Initialise the GPU image with the chunk's current pixels in the texture's byte order
+            let texture_format = config.texture_format();
+            let mut data = Vec::with_capacity((extent.x as usize) * (extent.y as usize) * 4);
+            extend_texture_rect(
+                &mut data,
+                cpu,
+                palette,
+                texture_format,
+                chunk_min,
+                extent,
+                extent.x as usize * 4,
+            );
+
+            let mut image = Image::new(
+                Extent3d {
+                    width: extent.x,
+                    height: extent.y,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                texture_format.texture_format(),
+                RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+            );
 
-            let src_u32 = &chunk[row_start + x0..row_start + x1];
+            // These textures will be updated during runtime
+            image.texture_descriptor.usage |= TextureUsages::COPY_DST;
 
-            for (i, &px) in src_u32.iter().enumerate() {
-                row_bytes[i * 4..i * 4 + 4].copy_from_slice(&utils::unpack_rgba8(px));
-            }
+            // Use nearest-neighbour sampling to avoid blurring pixel art
+            image.sampler = ImageSampler::nearest();
+
+            // Store image handle for later access.
+            let handle = images.add(image);
+            image_handles.push(handle.clone());
 
-            bytes.extend_from_slice(&row_bytes);
+            // Position the chunk sprites in a grid, placed on the canvas entity by the origin, anchor and pixel scale
+            let centre = config.pixel_to_local(chunk_min.as_vec2() + extent.as_vec2() / 2.0);
+            commands.spawn((
+                CanvasImage,
+                ChildOf(canvas),
+                Sprite::from_image(handle),
+                Transform::from_translation(centre.extend(config.canvas_z_index())).with_scale(config.sprite_scale()),
+            ));
         }
+    }
 
-        canvas_upload_ops.ops.push(CanvasUploadOp {
-            handle,
-            start: UVec2::new(padding_min_x, min_ex.y),
-            size: UVec2::new(padded_width, height),
-            bytes_per_row,
-            bytes,
+    image_handles
+}
+
+/// Append the texture bytes of a canvas rect (inside one chunk) to `bytes`, row by row.
+/// Each row is zero-padded to `bytes_per_row`; indexed pixels are resolved through the palette,
+/// and BGRA textures have red and blue swapped.
+#[inline]
+fn extend_texture_rect(
+    bytes: &mut Vec<u8>,
+    cpu: &CanvasCpuChunks,
+    palette: &CanvasPalette,
+    texture_format: CanvasTextureFormat,
+    min: UVec2,
+    size: UVec2,
+    bytes_per_row: usize,
+) {
+    // Locate the first pixel in CPU storage; rows follow at `row_stride`
+    let (buffer_index, first_index) = cpu.locate(min);
+    let row_stride = cpu.row_stride(buffer_index);
+    let width = size.x as usize;
+    let padding = bytes_per_row - width * 4;
+    let rows = (0..size.y as usize).map(|row| first_index + row * row_stride);
+
+    let bgra = texture_format.is_bgra();
+    let extend_pixels = if bgra {
+        utils::extend_bgra8_bytes
+    } else {
+        utils::extend_rgba8_bytes
+    };
+
+    match cpu.buffer(buffer_index) {
+        // Full-width unpadded rows are contiguous in storage, so the whole block is one cast
+        ChunkPixels::Rgba8(data) if width == row_stride && padding == 0 => {
+            extend_pixels(bytes, &data[first_index..first_index + width * size.y as usize]);
+        }
+        ChunkPixels::Rgba8(data) => {
+            for row_start in rows {
+                extend_pixels(bytes, &data[row_start..row_start + width]);
+                bytes.resize(bytes.len() + padding, 0);
+            }
+        }
+        ChunkPixels::Indexed8(data) => {
+            for row_start in rows {
+                for &index in &data[row_start..row_start + width] {
+                    let colour = palette.get(index);
+                    let colour = if bgra { utils::swizzle_bgra8(colour) } else { colour };
+                    bytes.extend_from_slice(&utils::unpack_rgba8(colour));
+                }
+                bytes.resize(bytes.len() + padding, 0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::uuid::Uuid, ecs::system::RunSystemOnce};
+
+    use super::*;
+    use crate::config::{CanvasAnchor, CanvasChunking, CanvasPixelFormat};
+
+    /// A 4x4 canvas of 2x2 chunks, with distinct image handles so upload ops can be told apart.
+    fn canvas(id: u128, pixel_format: CanvasPixelFormat) -> Canvas {
+        let mut canvas = Canvas::new(CanvasConfig {
+            canvas_size: UVec2::splat(4),
+            chunking: CanvasChunking::Count(UVec2::splat(2)),
+            clear_colour: 0,
+            pixel_format,
+            ..default()
         });
+        let handles = (0..4).map(|chunk| Handle::from(Uuid::from_u128(id * 16 + chunk))).collect();
+        canvas.images = CanvasImageHandles::new(canvas.config.num_chunks(), handles);
+        canvas
+    }
+
+    fn set_index(canvas: &mut Canvas, pos: UVec2, index: u8) {
+        let (buffer_index, i) = canvas.cpu.locate(pos);
+        let ChunkPixels::Indexed8(indices) = canvas.cpu.buffer_mut(buffer_index) else {
+            panic!("expected an indexed canvas");
+        };
+        indices[i] = index;
+    }
+
+    #[test]
+    fn palette_changes_reupload_chunks_using_the_entry() {
+        let mut world = World::new();
+        world.init_resource::<CanvasPalette>();
+        world.init_resource::<CanvasUploadOps>();
+
+        let mut a = canvas(1, CanvasPixelFormat::Indexed8);
+        set_index(&mut a, UVec2::new(2, 0), 5);
+        let mut b = canvas(2, CanvasPixelFormat::Indexed8);
+        set_index(&mut b, UVec2::new(1, 3), 5);
+        world.spawn(a);
+        world.spawn(b);
+        world.spawn(canvas(3, CanvasPixelFormat::Rgba8));
+
+        // Nothing is dirty, and no palette entry has changed yet
+        world.run_system_once(prepare_uploads).unwrap();
+        assert!(world.resource::<CanvasUploadOps>().ops.is_empty());
+
+        let red = utils::pack_rgba8([255, 0, 0, 255]);
+        world.resource_mut::<CanvasPalette>().set(5, red);
+        world.run_system_once(prepare_uploads).unwrap();
+
+        let ops = &world.resource::<CanvasUploadOps>().ops;
+        let mut uploaded: Vec<_> = ops.iter().map(|op| (op.handle.id(), op.start, op.size)).collect();
+        uploaded.sort_by_key(|(id, ..)| format!("{id:?}"));
+        let handle = |id: u128| Handle::<Image>::from(Uuid::from_u128(id)).id();
+        assert_eq!(
+            uploaded,
+            vec![
+                (handle(16 + 1), UVec2::ZERO, UVec2::splat(2)),
+                (handle(32 + 2), UVec2::ZERO, UVec2::splat(2))
+            ]
+        );
+
+        // Indices resolve through the palette: 5 is the new red, 0 is the default black
+        let black = [0, 0, 0, 255];
+        let op = ops.iter().find(|op| op.handle.id() == handle(16 + 1)).unwrap();
+        assert_eq!(op.bytes_per_row, 256);
+        assert_eq!(&op.bytes[..8], &[255, 0, 0, 255, 0, 0, 0, 255]);
+        assert_eq!(&op.bytes[256..264], &[black, black].concat()[..]);
+        let op = ops.iter().find(|op| op.handle.id() == handle(32 + 2)).unwrap();
+        assert_eq!(&op.bytes[256..264], &[0, 0, 0, 255, 255, 0, 0, 255]);
+
+        // The change is only applied once
+        world.run_system_once(prepare_uploads).unwrap();
+        assert!(world.resource::<CanvasUploadOps>().ops.is_empty());
+    }
+
+    #[test]
+    fn sim_grid_renders_only_changed_cells() {
+        let mut world = World::new();
+        world.spawn((
+            Canvas::new(CanvasConfig {
+                canvas_size: UVec2::new(10, 6),
+                chunking: CanvasChunking::Size(UVec2::splat(4)),
+                clear_colour: 0,
+                ..default()
+            }),
+            PrimaryCanvas,
+        ));
+        world.insert_resource(SimGrid::new(
+            UVec2::new(10, 6),
+            false,
+            |alive: &bool| if *alive { 7 } else { 0 },
+        ));
+
+        let render = |world: &mut World| {
+            world.run_system_once(render_sim_grid::<bool>).unwrap();
+            let mut canvas = world.query::<&mut Canvas>().single_mut(world).unwrap();
+            let dirty: Vec<_> = (0..canvas.dirty.len()).map(|i| canvas.dirty.take(i)).collect();
+            let pixels: Vec<_> = (0..6)
+                .flat_map(|y| (0..10).map(move |x| UVec2::new(x, y)))
+                .map(|pos| {
+                    let (buffer_index, index) = canvas.cpu.locate(pos);
+                    canvas.cpu.buffer(buffer_index).get(index)
+                })
+                .collect();
+            (dirty, pixels)
+        };
+
+        // The first render draws every cell
+        let (dirty, _) = render(&mut world);
+        assert!(dirty.iter().all(Option::is_some));
+
+        let alive = [UVec2::new(1, 1), UVec2::new(5, 4), UVec2::new(7, 4)];
+        world.resource_mut::<SimGrid<bool>>().step(|pos, _| alive.contains(&pos));
+        let (dirty, pixels) = render(&mut world);
+        let mut expected = vec![None; 6];
+        expected[0] = Some((UVec2::new(1, 1), UVec2::new(1, 1)));
+        expected[4] = Some((UVec2::new(1, 0), UVec2::new(3, 0)));
+        assert_eq!(dirty, expected);
+        for (i, px) in pixels.into_iter().enumerate() {
+            let pos = UVec2::new(i as u32 % 10, i as u32 / 10);
+            assert_eq!(px, if alive.contains(&pos) { 7 } else { 0 }, "{pos}");
+        }
+
+        // Cells that stay alive are not redrawn
+        world
+            .resource_mut::<SimGrid<bool>>()
+            .step(|pos, cells| pos != alive[0] && cells.as_slice()[(pos.y * 10 + pos.x) as usize]);
+        let (dirty, pixels) = render(&mut world);
+        let mut expected = vec![None; 6];
+        expected[0] = Some((UVec2::new(1, 1), UVec2::new(1, 1)));
+        assert_eq!(dirty, expected);
+        assert_eq!(pixels[11], 0);
+        assert_eq!(pixels[45], 7);
+
+        // Without a step nothing is redrawn
+        let (dirty, _) = render(&mut world);
+        assert!(dirty.iter().all(Option::is_none));
+    }
+
+    /// Every pixel of a canvas, row-major.
+    fn canvas_pixels(canvas: &Canvas) -> Vec<u32> {
+        let size = canvas.config.canvas_size();
+        (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(|pos| {
+                let (buffer_index, index) = canvas.cpu.locate(pos);
+                canvas.cpu.buffer(buffer_index).get(index)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resize_keeps_the_anchored_overlap() {
+        let value = |pos: UVec2| pos.y * 100 + pos.x + 1;
+        let cases = [
+            // Growing from the top-right corner moves the old content up and right
+            (
+                UVec2::new(14, 9),
+                CanvasChunking::Size(UVec2::splat(4)),
+                CanvasAnchor::TopRight,
+                IVec2::new(4, 3),
+            ),
+            // Shrinking about the centre crops evenly, rounding towards the bottom-left
+            (
+                UVec2::new(7, 4),
+                CanvasChunking::Count(UVec2::splat(2)),
+                CanvasAnchor::Centre,
+                IVec2::new(-2, -1),
+            ),
+        ];
+
+        for (size, chunking, anchor, offset) in cases {
+            let mut world = World::new();
+            world.init_resource::<Assets<Image>>();
+            world.init_resource::<CanvasPalette>();
+            world.init_resource::<Messages<ResizeCanvas>>();
+
+            let mut canvas = Canvas::new(CanvasConfig {
+                canvas_size: UVec2::new(10, 6),
+                chunking: CanvasChunking::Size(UVec2::splat(4)),
+                clear_colour: 0,
+                ..default()
+            });
+            for y in 0..6 {
+                for x in 0..10 {
+                    let (buffer_index, index) = canvas.cpu.locate(UVec2::new(x, y));
+                    canvas
+                        .cpu
+                        .buffer_mut(buffer_index)
+                        .map_run(index, 1, |_, _| value(UVec2::new(x, y)));
+                }
+            }
+            canvas.dirty.mark_canvas_rect(UVec2::ZERO, UVec2::new(10, 6));
+            world.spawn((canvas, PrimaryCanvas));
+
+            world.write_message(ResizeCanvas {
+                size,
+                chunking,
+                anchor,
+                ..default()
+            });
+            world.run_system_once(resize_canvas).unwrap();
+
+            let mut canvas = world.query::<&mut Canvas>().single_mut(&mut world).unwrap();
+            assert_eq!(canvas.config.canvas_size(), size, "{anchor:?}");
+            assert_eq!(canvas.config.chunking, chunking, "{anchor:?}");
+            let num_chunks = canvas.config.num_chunks();
+            assert_eq!(canvas.cpu.layout().num_chunks(), num_chunks, "{anchor:?}");
+            assert_eq!(canvas.dirty.len(), canvas.config.total_chunks(), "{anchor:?}");
+            assert!(
+                (0..canvas.dirty.len()).all(|i| canvas.dirty.take(i).is_none()),
+                "{anchor:?} starts clean"
+            );
+
+            let pixels = canvas_pixels(&canvas);
+            for (i, px) in pixels.iter().enumerate() {
+                let pos = UVec2::new(i as u32 % size.x, i as u32 / size.x);
+                let old = pos.as_ivec2() - offset;
+                let inside = old.cmpge(IVec2::ZERO).all() && old.cmplt(IVec2::new(10, 6)).all();
+                let expected = if inside { value(old.as_uvec2()) } else { 0 };
+                assert_eq!(*px, expected, "{anchor:?} {pos}");
+            }
+
+            // The new chunk images are initialised from the carried-over content
+            let layout = canvas.config.layout();
+            let handles = canvas.images.handles().to_vec();
+            assert_eq!(handles.len(), canvas.config.total_chunks(), "{anchor:?}");
+            let images = world.resource::<Assets<Image>>();
+            assert_eq!(images.len(), handles.len(), "{anchor:?}");
+            for (chunk, handle) in handles.iter().enumerate() {
+                let chunk_xy = layout.chunk_xy_of(chunk);
+                let (min, extent) = (layout.chunk_min(chunk_xy), layout.chunk_extent(chunk_xy));
+                let expected: Vec<u8> = (0..extent.y)
+                    .flat_map(|y| (0..extent.x).map(move |x| min + UVec2::new(x, y)))
+                    .flat_map(|pos| pixels[(pos.y * size.x + pos.x) as usize].to_le_bytes())
+                    .collect();
+                let image = images.get(handle).unwrap();
+                assert_eq!(image.data.as_deref(), Some(&expected[..]), "{anchor:?} chunk {chunk_xy}");
+            }
+        }
+    }
+
+    #[test]
+    fn texture_bytes_follow_the_texture_byte_order() {
+        let formats = [
+            (CanvasTextureFormat::Rgba8UnormSrgb, [255, 0, 0, 255]),
+            (CanvasTextureFormat::Rgba8Unorm, [255, 0, 0, 255]),
+            (CanvasTextureFormat::Bgra8UnormSrgb, [0, 0, 255, 255]),
+        ];
+        for (texture_format, expected) in formats {
+            for pixel_format in [CanvasPixelFormat::Rgba8, CanvasPixelFormat::Indexed8] {
+                let config = CanvasConfig {
+                    canvas_size: UVec2::new(3, 2),
+                    chunking: CanvasChunking::Count(UVec2::ONE),
+                    clear_colour: 0,
+                    pixel_format,
+                    texture_format,
+                    ..default()
+                };
+
+                // CPU pixels stay RGBA8 whatever the texture format
+                let red = config.pack_colour(Srgba::RED);
+                assert_eq!(utils::unpack_rgba8(red), [255, 0, 0, 255], "{texture_format:?}");
+
+                let mut palette = CanvasPalette::default();
+                palette.set(9, red);
+                let mut canvas = Canvas::new(config);
+                let (buffer_index, index) = canvas.cpu.locate(UVec2::new(1, 1));
+                let stored = if pixel_format == CanvasPixelFormat::Indexed8 { 9 } else { red };
+                canvas.cpu.buffer_mut(buffer_index).map_run(index, 1, |_, _| stored);
+
+                // Cleared pixels are index 0 (opaque black in the default palette) or transparent black
+                let clear = if pixel_format == CanvasPixelFormat::Indexed8 {
+                    [0, 0, 0, 255]
+                } else {
+                    [0; 4]
+                };
+
+                // Unpadded rows take the whole-block path, padded rows are copied one by one
+                for bytes_per_row in [12, 16] {
+                    let mut bytes = Vec::new();
+                    extend_texture_rect(
+                        &mut bytes,
+                        &canvas.cpu,
+                        &palette,
+                        texture_format,
+                        UVec2::ZERO,
+                        UVec2::new(3, 2),
+                        bytes_per_row,
+                    );
+                    let padding = vec![0; bytes_per_row - 12];
+                    let expected = [&clear[..], &clear, &clear, &padding, &clear, &expected, &clear, &padding].concat();
+                    assert_eq!(bytes, expected, "{texture_format:?} {pixel_format:?} {bytes_per_row}");
+                }
+            }
+        }
     }
 }
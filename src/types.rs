@@ -1,16 +1,40 @@
 //! Internal types and pure geometry helpers.
 
-use bevy::{math::U8Vec2, prelude::*};
+use core::ops::Range;
+
+use bevy::prelude::*;
+
+use super::{
+    config::{CanvasPixelFormat, WrapMode},
+    options::DrawOptions,
+};
+
+/// Moore neighbourhood offsets, row-major.
+pub const MOORE: [IVec2; 8] = [
+    IVec2::new(-1, -1),
+    IVec2::new(0, -1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(-1, 1),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+];
+
+/// Von Neumann neighbourhood offsets, row-major.
+pub const VON_NEUMANN: [IVec2; 4] = [IVec2::new(0, -1), IVec2::new(-1, 0), IVec2::new(1, 0), IVec2::new(0, 1)];
 
 /// Canvas geometry helper; encapsulates common coordinate transforms:
 /// - wrapping toroidally within the canvas
 /// - locating which chunk a pixel belongs to
 /// - computing chunk-local coordinates
+///
+/// Chunks are `chunk_size` pixels, except the last column and row, which take whatever remains of the canvas.
 #[derive(Clone, Copy)]
 pub struct CanvasLayout {
     /// Full canvas size in pixels.
     pub canvas_size: UVec2,
-    /// Chunk size in pixels.
+    /// Nominal chunk size in pixels (edge chunks may be smaller).
     pub chunk_size: UVec2,
 }
 
@@ -23,10 +47,32 @@ impl CanvasLayout {
         Self { canvas_size, chunk_size }
     }
 
-    /// Number of chunks in each axis.
+    /// Number of chunks in each axis, including ragged edge chunks.
+    #[inline]
+    pub fn num_chunks(self) -> UVec2 {
+        UVec2::new(
+            self.canvas_size.x.div_ceil(self.chunk_size.x),
+            self.canvas_size.y.div_ceil(self.chunk_size.y),
+        )
+    }
+
+    /// Linear chunk index (row-major chunk ordering) of a chunk coordinate.
+    #[inline]
+    pub fn chunk_index(self, chunk_xy: UVec2) -> usize {
+        chunk_xy.y as usize * self.num_chunks().x as usize + chunk_xy.x as usize
+    }
+
+    /// Chunk coordinate of a linear chunk index.
     #[inline]
-    pub fn num_chunks(self) -> U8Vec2 {
-        (self.canvas_size / self.chunk_size).as_u8vec2()
+    pub fn chunk_xy_of(self, chunk_index: usize) -> UVec2 {
+        let num_chunks_x = self.num_chunks().x as usize;
+        UVec2::new((chunk_index % num_chunks_x) as u32, (chunk_index / num_chunks_x) as u32)
+    }
+
+    /// Actual size of a chunk in pixels, which is smaller than `chunk_size` for ragged edge chunks.
+    #[inline]
+    pub fn chunk_extent(self, chunk_xy: UVec2) -> UVec2 {
+        (self.canvas_size - self.chunk_min(chunk_xy)).min(self.chunk_size)
     }
 
     /// Toroidal wrap within canvas bounds.
@@ -35,12 +81,12 @@ impl CanvasLayout {
         pos % self.canvas_size
     }
 
-    /// Convert a chunk coordinate into a compact `U8Vec2` key.
-    /// Chunk count must fit within 8-bit per axis (<=255).
+    /// Offset a wrapped pixel by `delta`, wrapping toroidally.
     #[inline]
-    pub fn chunk_key(self, chunk_xy: UVec2) -> U8Vec2 {
-        debug_assert!(chunk_xy.x < 256 && chunk_xy.y < 256);
-        U8Vec2::new(chunk_xy.x as u8, chunk_xy.y as u8)
+    pub fn offset(self, wrapped_pos: UVec2, delta: IVec2) -> UVec2 {
+        let size = self.canvas_size.as_i64vec2();
+        let pos = wrapped_pos.as_i64vec2() + delta.as_i64vec2();
+        pos.rem_euclid(size).as_uvec2()
     }
 
     /// Chunk coordinate (in chunk grid space) for a wrapped pixel.
@@ -73,6 +119,369 @@ impl CanvasLayout {
         let until_chunk_row_end = self.chunk_size.x - local.x;
         until_canvas_row_end.min(until_chunk_row_end)
     }
+
+    /// Split a toroidally wrapped rect into boundary-safe runs, in row-major order from `start`.
+    ///
+    /// Yields the wrapped start of each run and its length; every run satisfies `max_run_len`.
+    pub fn rect_runs(self, start: UVec2, size: UVec2) -> impl Iterator<Item = (UVec2, u32)> {
+        (0..size.y).flat_map(move |row| {
+            let y = (start.y + row) % self.canvas_size.y;
+            let mut x = start.x % self.canvas_size.x;
+            let mut remaining = size.x;
+            core::iter::from_fn(move || {
+                if remaining == 0 {
+                    return None;
+                }
+                let pos = UVec2::new(x, y);
+                let run = remaining.min(self.max_run_len(pos));
+                remaining -= run;
+                x += run;
+                if x == self.canvas_size.x {
+                    x = 0;
+                }
+                Some((pos, run))
+            })
+        })
+    }
+}
+
+/// Split a toroidally wrapped 1D range into at most two non-wrapping ranges within `0..size`.
+///
+/// The second range is empty unless the input crosses the end of the axis.
+/// Lengths of `size` or more cover the whole axis once.
+#[inline]
+pub fn wrap_range(start: u32, len: u32, size: u32) -> [Range<u32>; 2] {
+    if len >= size {
+        return [0..size, 0..0];
+    }
+
+    let start = start % size;
+    let end = start + len;
+    if end <= size {
+        [start..end, 0..0]
+    } else {
+        [start..size, 0..end - size]
+    }
+}
+
+/// How the canvas pixels of an `AxisSegment` step along the axis.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SegmentStep {
+    /// Increasing canvas coordinates.
+    Forward,
+    /// Decreasing canvas coordinates (mirrored).
+    Reverse,
+    /// Every pixel maps to the same canvas coordinate (clamped).
+    Repeat,
+}
+
+/// A piece of a 1D range resolved through a `WrapMode`.
+///
+/// Source pixels `src..src + len` map to canvas pixels starting at `dst`, stepping by `step`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AxisSegment {
+    pub src: u32,
+    pub dst: u32,
+    pub len: u32,
+    pub step: SegmentStep,
+}
+
+/// Split the 1D range `start..start + len` on an axis of `size` pixels into segments resolved through `mode`.
+///
+/// Segments never cross the canvas edges and are yielded in source order; clipped pixels are skipped.
+pub fn axis_segments(start: i64, len: u32, size: u32, mode: WrapMode) -> impl Iterator<Item = AxisSegment> {
+    let size_i = size as i64;
+    let mut offset = 0u32;
+    core::iter::from_fn(move || {
+        while offset < len {
+            let coord = start + offset as i64;
+            let remaining = len - offset;
+            let src = offset;
+
+            // Pixels before the canvas, up to the first in-bounds coordinate
+            let before = |remaining: u32| (-coord).min(remaining as i64) as u32;
+            let (segment, consumed) = match mode {
+                WrapMode::Wrap => {
+                    let dst = coord.rem_euclid(size_i) as u32;
+                    let len = remaining.min(size - dst);
+                    (Some((dst, len, SegmentStep::Forward)), len)
+                }
+                WrapMode::Clip if coord < 0 => (None, before(remaining)),
+                WrapMode::Clip if coord >= size_i => (None, remaining),
+                WrapMode::Clamp if coord < 0 => {
+                    let len = before(remaining);
+                    (Some((0, len, SegmentStep::Repeat)), len)
+                }
+                WrapMode::Clamp if coord >= size_i => (Some((size - 1, remaining, SegmentStep::Repeat)), remaining),
+                WrapMode::Clip | WrapMode::Clamp => {
+                    let len = remaining.min((size_i - coord) as u32);
+                    (Some((coord as u32, len, SegmentStep::Forward)), len)
+                }
+                WrapMode::Mirror => {
+                    let reflected = coord.rem_euclid(2 * size_i) as u32;
+                    if reflected < size {
+                        let len = remaining.min(size - reflected);
+                        (Some((reflected, len, SegmentStep::Forward)), len)
+                    } else {
+                        let dst = 2 * size - 1 - reflected;
+                        let len = remaining.min(dst + 1);
+                        (Some((dst, len, SegmentStep::Reverse)), len)
+                    }
+                }
+            };
+
+            offset += consumed;
+            if let Some((dst, len, step)) = segment {
+                return Some(AxisSegment { src, dst, len, step });
+            }
+        }
+        None
+    })
+}
+
+/// Canvas pixels hit when the 1D range `range` on an axis of `size` pixels is resolved through `mode`.
+///
+/// Returns at most two ranges within `0..size` that cover each hit pixel once, however long the input is.
+pub fn axis_cover(range: Range<i64>, size: u32, mode: WrapMode) -> [Range<u32>; 2] {
+    if range.is_empty() {
+        return [0..0, 0..0];
+    }
+
+    let size_i = size as i64;
+    let len = range.end - range.start;
+    match mode {
+        WrapMode::Clip => [range.start.clamp(0, size_i) as u32..range.end.clamp(0, size_i) as u32, 0..0],
+        WrapMode::Clamp => {
+            let last = size_i - 1;
+            [
+                range.start.clamp(0, last) as u32..(range.end - 1).clamp(0, last) as u32 + 1,
+                0..0,
+            ]
+        }
+        WrapMode::Wrap if len >= size_i => [0..size, 0..0],
+        WrapMode::Wrap => wrap_range(range.start.rem_euclid(size_i) as u32, len as u32, size),
+        WrapMode::Mirror if len >= 2 * size_i => [0..size, 0..0],
+        WrapMode::Mirror => {
+            // Reflection moves by at most one pixel per step, so the image is one contiguous range, extended to an
+            // edge wherever the input turns there
+            let mirror = |coord| mode.resolve(coord, size).unwrap_or_default();
+            let first = mirror(range.start);
+            let last = mirror(range.end - 1);
+            let min = if contains_residue(&range, 0, 2 * size_i) || contains_residue(&range, 2 * size_i - 1, 2 * size_i) {
+                0
+            } else {
+                first.min(last)
+            };
+            let max = if contains_residue(&range, size_i - 1, 2 * size_i) || contains_residue(&range, size_i, 2 * size_i) {
+                size - 1
+            } else {
+                first.max(last)
+            };
+            [min..max + 1, 0..0]
+        }
+    }
+}
+
+/// The coordinate in `range` that `mode` resolves to `target`, nearest to `centre`.
+///
+/// Returns `None` when no coordinate in `range` resolves to `target`.
+pub fn nearest_preimage(target: u32, range: Range<i64>, size: u32, mode: WrapMode, centre: f64) -> Option<i64> {
+    if range.is_empty() {
+        return None;
+    }
+
+    let size_i = size as i64;
+    let target_i = target as i64;
+    let distance = |coord: i64| (coord as f64 + 0.5 - centre).abs();
+    let nearest = |a: Option<i64>, b: Option<i64>| match (a, b) {
+        (Some(a), Some(b)) => Some(if distance(b) < distance(a) { b } else { a }),
+        (a, b) => a.or(b),
+    };
+
+    // Nearest to `centre` within `range` of the coordinates congruent to `residue` modulo `period`
+    let nearest_in_family = |residue: i64, period: i64| {
+        let pivot = (centre.floor() as i64).clamp(range.start, range.end - 1);
+        let below = pivot - (pivot - residue).rem_euclid(period);
+        let above = below + period;
+        nearest(
+            range.contains(&below).then_some(below),
+            range.contains(&above).then_some(above),
+        )
+    };
+
+    match mode {
+        WrapMode::Clip => range.contains(&target_i).then_some(target_i),
+        WrapMode::Wrap => nearest_in_family(target_i, size_i),
+        WrapMode::Mirror => nearest(
+            nearest_in_family(target_i, 2 * size_i),
+            nearest_in_family(2 * size_i - 1 - target_i, 2 * size_i),
+        ),
+        WrapMode::Clamp => {
+            // Edge pixels also take every coordinate beyond their edge
+            let start = if target == 0 { range.start } else { range.start.max(target_i) };
+            let end = if target == size - 1 {
+                range.end
+            } else {
+                range.end.min(target_i + 1)
+            };
+            (start < end).then(|| (centre.floor() as i64).clamp(start, end - 1))
+        }
+    }
+}
+
+/// Whether `range` contains a coordinate congruent to `residue` modulo `period`.
+#[inline]
+fn contains_residue(range: &Range<i64>, residue: i64, period: i64) -> bool {
+    range.start + (residue - range.start).rem_euclid(period) < range.end
+}
+
+/// CPU pixel storage for a single chunk (row-major).
+pub enum ChunkPixels {
+    /// Packed RGBA8 pixels.
+    Rgba8(Vec<u32>),
+    /// Palette indices.
+    Indexed8(Vec<u8>),
+}
+
+impl ChunkPixels {
+    /// Allocate `len` pixels in the given format, all set to `value`.
+    pub fn new(format: CanvasPixelFormat, len: usize, value: u32) -> Self {
+        match format {
+            CanvasPixelFormat::Rgba8 => Self::Rgba8(vec![value; len]),
+            CanvasPixelFormat::Indexed8 => Self::Indexed8(vec![value as u8; len]),
+        }
+    }
+
+    /// Number of pixels.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Rgba8(data) => data.len(),
+            Self::Indexed8(data) => data.len(),
+        }
+    }
+
+    /// Set every pixel to `value`, combined through `options`.
+    #[inline]
+    pub fn fill(&mut self, value: u32, options: &DrawOptions) {
+        if options.is_copy() {
+            match self {
+                Self::Rgba8(data) => data.fill(value),
+                Self::Indexed8(data) => data.fill(value as u8),
+            }
+            return;
+        }
+
+        match self {
+            Self::Rgba8(data) => options.apply_fill(data, value),
+            Self::Indexed8(data) => {
+                for dst in data.iter_mut() {
+                    *dst = options.apply_index(*dst as u32, value) as u8;
+                }
+            }
+        }
+    }
+
+    /// Read one pixel; palette indices are widened to `u32`.
+    #[inline]
+    pub fn get(&self, index: usize) -> u32 {
+        match self {
+            Self::Rgba8(data) => data[index],
+            Self::Indexed8(data) => data[index] as u32,
+        }
+    }
+
+    /// Copy pixels starting at `index` into `dst`; palette indices are widened to `u32`.
+    #[inline]
+    pub fn read(&self, index: usize, dst: &mut [u32]) {
+        match self {
+            Self::Rgba8(data) => dst.copy_from_slice(&data[index..index + dst.len()]),
+            Self::Indexed8(data) => {
+                let src = &data[index..index + dst.len()];
+                for (dst, &value) in dst.iter_mut().zip(src) {
+                    *dst = value as u32;
+                }
+            }
+        }
+    }
+
+    /// Rewrite `len` pixels starting at `index` in place; `f` receives the run offset and old value.
+    #[inline]
+    pub fn map_run(&mut self, index: usize, len: usize, f: impl FnMut(usize, u32) -> u32) {
+        self.as_slice_mut().map_run(index, len, f);
+    }
+
+    /// Borrow the whole buffer as a mutable slice view.
+    #[inline]
+    pub fn as_slice_mut(&mut self) -> PixelSliceMut<'_> {
+        match self {
+            Self::Rgba8(data) => PixelSliceMut::Rgba8(data),
+            Self::Indexed8(data) => PixelSliceMut::Indexed8(data),
+        }
+    }
+
+    /// Write `src` into the buffer starting at `index`, combined through `options`.
+    /// Indexed storage keeps only the low 8 bits of each value.
+    #[inline]
+    pub fn write(&mut self, index: usize, src: &[u32], options: &DrawOptions) {
+        match self {
+            Self::Rgba8(data) => {
+                let dst = &mut data[index..index + src.len()];
+                if options.is_copy() {
+                    dst.copy_from_slice(src);
+                } else {
+                    options.apply_run(dst, src);
+                }
+            }
+            Self::Indexed8(data) => {
+                for (dst, &value) in data[index..index + src.len()].iter_mut().zip(src) {
+                    *dst = options.apply_index(*dst as u32, value) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Mutable view over part of a pixel buffer.
+pub enum PixelSliceMut<'a> {
+    /// Packed RGBA8 pixels.
+    Rgba8(&'a mut [u32]),
+    /// Palette indices.
+    Indexed8(&'a mut [u8]),
+}
+
+impl PixelSliceMut<'_> {
+    /// Rewrite `len` pixels starting at `index` in place; `f` receives the run offset and old value.
+    /// Palette indices are widened to `u32` and narrowed back to their low 8 bits.
+    #[inline]
+    pub fn map_run(&mut self, index: usize, len: usize, mut f: impl FnMut(usize, u32) -> u32) {
+        match self {
+            Self::Rgba8(data) => {
+                for (i, px) in data[index..index + len].iter_mut().enumerate() {
+                    *px = f(i, *px);
+                }
+            }
+            Self::Indexed8(data) => {
+                for (i, px) in data[index..index + len].iter_mut().enumerate() {
+                    *px = f(i, *px as u32) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// An independently mutable block of CPU storage, used to split work across tasks.
+///
+/// With chunked storage a block is one chunk; with contiguous storage it is one full row of chunks.
+pub struct StorageBlock<'a> {
+    /// Block pixels, row-major with `stride`.
+    pub pixels: PixelSliceMut<'a>,
+    /// Row stride in pixels.
+    pub stride: usize,
+    /// Canvas position of the first pixel in the block.
+    pub origin: UVec2,
+    /// Block extent in pixels; always a whole number of chunks.
+    pub size: UVec2,
 }
 
 /// Dirty rectangle for one chunk (chunk-local pixel space).
@@ -95,10 +504,101 @@ impl Default for DirtyRect {
     }
 }
 
+impl DirtyRect {
+    /// Union a chunk-local rect into this dirty rect, clamped to `chunk_size`.
+    /// - `min` is inclusive
+    /// - `size` is extent (width/height)
+    #[inline(always)]
+    pub fn mark(&mut self, min: UVec2, size: UVec2, chunk_size: UVec2) {
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
+        // Clamp to chunk bounds defensively.
+        let max_bound = chunk_size - UVec2::ONE;
+        let min = min.min(max_bound);
+        let max = (min + size - UVec2::ONE).min(max_bound);
+
+        if !self.dirty {
+            self.dirty = true;
+            self.min = min;
+            self.max = max;
+        } else {
+            self.min = self.min.min(min);
+            self.max = self.max.max(max);
+        }
+    }
+}
+
+/// Mark a rect dirty across a row-major grid of chunk dirty rects.
+///
+/// `min` is relative to the first chunk in `rects`, and the rect must lie inside the grid.
+pub fn mark_chunk_rects(rects: &mut [DirtyRect], chunks_per_row: u32, chunk_size: UVec2, min: UVec2, size: UVec2) {
+    if size.x == 0 || size.y == 0 {
+        return;
+    }
+
+    let max = min + size;
+    let first = min / chunk_size;
+    let last = (max - UVec2::ONE) / chunk_size;
+    for cy in first.y..=last.y {
+        for cx in first.x..=last.x {
+            let chunk_min = UVec2::new(cx, cy) * chunk_size;
+            let local_min = min.max(chunk_min) - chunk_min;
+            let local_max = max.min(chunk_min + chunk_size) - chunk_min;
+            rects[(cy * chunks_per_row + cx) as usize].mark(local_min, local_max - local_min, chunk_size);
+        }
+    }
+}
+
+/// Dirty tracking handle for a single chunk, handed to per-chunk callbacks.
+///
+/// Marked regions are in chunk-local pixel coordinates and are uploaded with the next frame's ops.
+pub struct ChunkDirty<'a> {
+    rect: &'a mut DirtyRect,
+    origin: UVec2,
+    chunk_size: UVec2,
+}
+
+impl<'a> ChunkDirty<'a> {
+    /// Wrap a chunk's dirty rect; `chunk_size` is the chunk's actual extent.
+    #[inline]
+    pub fn new(rect: &'a mut DirtyRect, origin: UVec2, chunk_size: UVec2) -> Self {
+        Self {
+            rect,
+            origin,
+            chunk_size,
+        }
+    }
+
+    /// Mark a chunk-local rect as dirty, unioned with anything already marked.
+    #[inline]
+    pub fn mark_rect(&mut self, min: UVec2, size: UVec2) {
+        self.rect.mark(min, size, self.chunk_size);
+    }
+
+    /// Mark the whole chunk as dirty.
+    #[inline]
+    pub fn mark_all(&mut self) {
+        self.rect.mark(UVec2::ZERO, self.chunk_size, self.chunk_size);
+    }
+
+    /// Chunk size in pixels (smaller than the nominal chunk size for ragged edge chunks).
+    #[inline]
+    pub fn chunk_size(&self) -> UVec2 {
+        self.chunk_size
+    }
+
+    /// Canvas position of the chunk's first pixel.
+    #[inline]
+    pub fn origin(&self) -> UVec2 {
+        self.origin
+    }
+}
+
 /// A single GPU upload operation for a chunk image.
 ///
-/// `bytes` contains tightly packed rows with `bytes_per_row` stride (aligned).
-#[derive(Clone)]
+/// `bytes` contains rows of `size.x` pixels at `bytes_per_row` stride (aligned), zero-padded where needed.
 pub struct CanvasUploadOp {
     /// Handle to the chunk image to upload to.
     pub handle: Handle<Image>,
@@ -108,6 +608,142 @@ pub struct CanvasUploadOp {
     pub size: UVec2,
     /// Row stride in bytes (must be aligned as required by wgpu).
     pub bytes_per_row: u32,
-    /// Raw bytes sent to the GPU (RGBA8).
+    /// Raw bytes sent to the GPU (RGBA8, palette indices already resolved).
     pub bytes: Vec<u8>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [WrapMode; 4] = [WrapMode::Wrap, WrapMode::Clip, WrapMode::Clamp, WrapMode::Mirror];
+
+    fn segment(src: u32, dst: u32, len: u32, step: SegmentStep) -> AxisSegment {
+        AxisSegment { src, dst, len, step }
+    }
+
+    #[test]
+    fn axis_segments_cross_both_edges() {
+        use SegmentStep::{Forward, Repeat, Reverse};
+
+        let cases = [
+            (
+                WrapMode::Wrap,
+                vec![
+                    segment(0, 2, 2, Forward),
+                    segment(2, 0, 4, Forward),
+                    segment(6, 0, 2, Forward),
+                ],
+            ),
+            (WrapMode::Clip, vec![segment(2, 0, 4, Forward)]),
+            (
+                WrapMode::Clamp,
+                vec![segment(0, 0, 2, Repeat), segment(2, 0, 4, Forward), segment(6, 3, 2, Repeat)],
+            ),
+            (
+                WrapMode::Mirror,
+                vec![
+                    segment(0, 1, 2, Reverse),
+                    segment(2, 0, 4, Forward),
+                    segment(6, 3, 2, Reverse),
+                ],
+            ),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(axis_segments(-2, 8, 4, mode).collect::<Vec<_>>(), expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn axis_segments_match_resolve() {
+        for mode in MODES {
+            for start in -9..9 {
+                for len in 0..12 {
+                    let mut resolved = vec![None; len as usize];
+                    for s in axis_segments(start, len, 4, mode) {
+                        for i in 0..s.len {
+                            let dst = match s.step {
+                                SegmentStep::Forward => s.dst + i,
+                                SegmentStep::Reverse => s.dst - i,
+                                SegmentStep::Repeat => s.dst,
+                            };
+                            resolved[(s.src + i) as usize] = Some(dst);
+                        }
+                    }
+                    let expected: Vec<_> = (0..len).map(|i| mode.resolve(start + i as i64, 4)).collect();
+                    assert_eq!(resolved, expected, "{mode:?} {start} {len}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn axis_cover_hits_each_resolved_pixel_once() {
+        for mode in MODES {
+            for start in -9..9 {
+                for len in 0..12 {
+                    let range = start..start + len;
+                    let mut expected = [false; 4];
+                    for coord in range.clone() {
+                        if let Some(pixel) = mode.resolve(coord, 4) {
+                            expected[pixel as usize] = true;
+                        }
+                    }
+
+                    let mut covered = [0; 4];
+                    for pixels in axis_cover(range.clone(), 4, mode) {
+                        for pixel in pixels {
+                            covered[pixel as usize] += 1;
+                        }
+                    }
+                    assert_eq!(covered, expected.map(u32::from), "{mode:?} {range:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn axis_cover_is_bounded_for_huge_ranges() {
+        let huge = i32::MIN as i64..i32::MAX as i64;
+        assert_eq!(axis_cover(huge.clone(), 4, WrapMode::Wrap), [0..4, 0..0]);
+        assert_eq!(axis_cover(huge.clone(), 4, WrapMode::Clip), [0..4, 0..0]);
+        assert_eq!(axis_cover(huge.clone(), 4, WrapMode::Clamp), [0..4, 0..0]);
+        assert_eq!(axis_cover(huge, 4, WrapMode::Mirror), [0..4, 0..0]);
+    }
+
+    #[test]
+    fn nearest_preimage_is_nearest_to_centre() {
+        for mode in MODES {
+            for start in -9..9 {
+                for len in 0..12 {
+                    let range = start..start + len;
+                    for centre in [-20.0, -3.5, 0.25, 2.0, 7.75, 20.0] {
+                        for target in 0..4 {
+                            let distance = |coord: i64| (coord as f64 + 0.5 - centre).abs();
+                            let best = range
+                                .clone()
+                                .filter(|&coord| mode.resolve(coord, 4) == Some(target))
+                                .map(distance)
+                                .min_by(f64::total_cmp);
+
+                            let found = nearest_preimage(target, range.clone(), 4, mode, centre);
+                            assert_eq!(found.map(distance), best, "{mode:?} {range:?} {centre} {target}");
+                            if let Some(coord) = found {
+                                assert!(range.contains(&coord));
+                                assert_eq!(mode.resolve(coord, 4), Some(target));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrap_range_splits_at_the_end() {
+        assert_eq!(wrap_range(1, 2, 4), [1..3, 0..0]);
+        assert_eq!(wrap_range(3, 3, 4), [3..4, 0..2]);
+        assert_eq!(wrap_range(6, 1, 4), [2..3, 0..0]);
+        assert_eq!(wrap_range(2, 9, 4), [0..4, 0..0]);
+    }
+}
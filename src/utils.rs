@@ -17,3 +17,31 @@ pub fn pack_rgba8(colour: [u8; 4]) -> u32 {
 pub fn unpack_rgba8(colour: u32) -> [u8; 4] {
     colour.to_le_bytes()
 }
+
+/// Swap the red and blue channels of a packed pixel (RGBA8 <-> BGRA8).
+#[inline(always)]
+pub fn swizzle_bgra8(colour: u32) -> u32 {
+    (colour & 0xff00_ff00) | ((colour & 0x0000_00ff) << 16) | ((colour >> 16) & 0x0000_00ff)
+}
+
+/// Append packed pixels to `bytes` as raw RGBA8.
+///
+/// On little-endian targets the `u32` memory already is RGBA8, so rows are cast rather than converted.
+#[inline(always)]
+pub fn extend_rgba8_bytes(bytes: &mut Vec<u8>, pixels: &[u32]) {
+    if cfg!(target_endian = "little") {
+        bytes.extend_from_slice(bytemuck::cast_slice(pixels));
+    } else {
+        for &px in pixels {
+            bytes.extend_from_slice(&unpack_rgba8(px));
+        }
+    }
+}
+
+/// Append packed pixels to `bytes` as raw BGRA8.
+#[inline(always)]
+pub fn extend_bgra8_bytes(bytes: &mut Vec<u8>, pixels: &[u32]) {
+    for &px in pixels {
+        bytes.extend_from_slice(&unpack_rgba8(swizzle_bgra8(px)));
+    }
+}
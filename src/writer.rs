@@ -0,0 +1,452 @@
+//! CPU write path shared by every draw operation.
+//!
+//! All writes are decomposed into runs that never cross a canvas row end or a chunk row end,
+//! which are then copied into the CPU chunks and unioned into the chunk dirty rects.
+//! Pixels outside the canvas are resolved per axis by the canvas's `WrapMode`s.
+
+use core::ops::Range;
+
+use bevy::prelude::*;
+
+use super::{
+    config::CanvasWrap,
+    options::DrawOptions,
+    quantise::CanvasQuantiser,
+    resources::{CanvasCpuChunks, CanvasDirtyRects},
+    shapes::CanvasShape,
+    types::{CanvasLayout, SegmentStep, axis_cover, axis_segments, nearest_preimage},
+};
+
+/// Pixels quantised per batch, bounding the on-stack scratch buffer.
+const QUANTISE_BATCH: usize = 64;
+
+/// Borrowed view over the CPU chunks and dirty rects that performs canvas writes.
+pub struct CanvasWriter<'a> {
+    cpu: &'a mut CanvasCpuChunks,
+    dirty: &'a mut CanvasDirtyRects,
+    layout: CanvasLayout,
+    wrap: CanvasWrap,
+    quantiser: Option<&'a CanvasQuantiser>,
+}
+
+impl<'a> CanvasWriter<'a> {
+    /// Create a writer.
+    /// `quantiser` is applied to every written pixel, and must be `None` for indexed canvases.
+    #[inline]
+    pub fn new(
+        cpu: &'a mut CanvasCpuChunks,
+        dirty: &'a mut CanvasDirtyRects,
+        layout: CanvasLayout,
+        wrap: CanvasWrap,
+        quantiser: Option<&'a CanvasQuantiser>,
+    ) -> Self {
+        Self {
+            cpu,
+            dirty,
+            layout,
+            wrap,
+            quantiser,
+        }
+    }
+
+    /// Clear the entire canvas to a single colour.
+    /// This fills every CPU buffer and marks each chunk fully dirty.
+    #[inline]
+    pub fn clear(&mut self, rgba_u32: u32, options: &DrawOptions) {
+        // Quantisation is position dependent, so dithered clears go through the run writer
+        if self.quantiser.is_some() {
+            let row = vec![rgba_u32; self.layout.canvas_size.x as usize];
+            for y in 0..self.layout.canvas_size.y {
+                self.write_runs(UVec2::new(0, y), &row, options);
+            }
+            return;
+        }
+
+        let num_chunks = self.layout.num_chunks();
+
+        // Fill every CPU buffer
+        for buffer in self.cpu.buffers_mut().iter_mut() {
+            buffer.fill(rgba_u32, options);
+        }
+
+        // Mark every chunk as fully dirty
+        for y in 0..num_chunks.y {
+            for x in 0..num_chunks.x {
+                let chunk_key = UVec2::new(x, y);
+                let extent = self.layout.chunk_extent(chunk_key);
+                self.dirty.mark_rect(&chunk_key, UVec2::ZERO, extent);
+            }
+        }
+    }
+
+    /// Draw a single pixel.
+    #[inline]
+    pub fn pixel(&mut self, pos: IVec2, rgba_u32: u32, options: &DrawOptions) {
+        self.write_run(pos, core::slice::from_ref(&rgba_u32), options);
+    }
+
+    /// Draw many independent pixels.
+    #[inline]
+    pub fn pixels(&mut self, positions: &[IVec2], rgba_u32: &[u32], options: &DrawOptions) {
+        debug_assert_eq!(positions.len(), rgba_u32.len());
+
+        for (pos, colour) in positions.iter().copied().zip(rgba_u32.iter().copied()) {
+            self.write_run(pos, core::slice::from_ref(&colour), options);
+        }
+    }
+
+    /// Draw a row-major stream starting at `start`.
+    ///
+    /// Rows are aligned to canvas-width periods: the first runs from `start.x` to the end of the period containing it
+    /// (the canvas row end for an in-bounds start), and later rows span X = 0 to the canvas width, so no row is longer
+    /// than the canvas. Each row is resolved like a `rect` row: columns by the X wrap mode, and rows past the canvas
+    /// edges by the Y wrap mode.
+    #[inline]
+    pub fn span(&mut self, start: IVec2, src_u32: &[u32], options: &DrawOptions) {
+        if src_u32.is_empty() {
+            return;
+        }
+
+        let layout = self.layout;
+        let width = layout.canvas_size.x as i64;
+        let mut x = start.x as i64;
+        let mut row = start.y as i64;
+
+        let mut remaining = src_u32;
+        while !remaining.is_empty() {
+            let row_end = (x.div_euclid(width) + 1) * width;
+            let (row_src, rest) = remaining.split_at(remaining.len().min((row_end - x) as usize));
+            match self.wrap.y.resolve(row, layout.canvas_size.y) {
+                Some(y) => self.write_row(x, y, row_src, options),
+                // Rows only move further past the bottom edge from here
+                None if row >= layout.canvas_size.y as i64 => return,
+                None => {}
+            }
+
+            remaining = rest;
+            x = 0;
+            row += 1;
+        }
+    }
+
+    /// Draw a row-major rectangle, resolving pixels outside the canvas by the wrap modes.
+    #[inline]
+    pub fn rect(&mut self, start: IVec2, size: UVec2, src_u32: &[u32], options: &DrawOptions) {
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+        debug_assert_eq!(src_u32.len(), (size.x * size.y) as usize);
+
+        // Source row stride is the rect width
+        let row_stride = size.x as usize;
+
+        for (row, src_row) in src_u32.chunks_exact(row_stride).enumerate() {
+            if let Some(y) = self.wrap.y.resolve(start.y as i64 + row as i64, self.layout.canvas_size.y) {
+                self.write_row(start.x as i64, y, src_row, options);
+            }
+        }
+    }
+
+    /// Fill a shape with a single colour, resolving pixels outside the canvas by the wrap modes.
+    ///
+    /// Each canvas pixel the shape resolves to is written once, so the work is bounded by the canvas size however large
+    /// the shape is. Shapes with non-finite coordinates are skipped.
+    #[inline]
+    pub fn shape(&mut self, shape: CanvasShape, rgba_u32: u32, options: &DrawOptions) {
+        if !shape.is_finite() {
+            return;
+        }
+
+        let size = self.layout.canvas_size;
+        let rows = shape.rows();
+        let mut row = Vec::new();
+        for ys in axis_cover(rows.clone(), size.y, self.wrap.y) {
+            for y in ys {
+                // The shape row nearest the centre is the widest of those landing on `y`
+                let Some(shape_y) = nearest_preimage(y, rows.clone(), size.y, self.wrap.y, shape.centre_y()) else {
+                    continue;
+                };
+                self.fill_columns(
+                    y,
+                    axis_cover(shape.columns(shape_y), size.x, self.wrap.x),
+                    &mut row,
+                    rgba_u32,
+                    options,
+                );
+            }
+        }
+    }
+
+    /// Fill a rectangle with a single colour, resolving pixels outside the canvas by the wrap modes.
+    ///
+    /// As with `shape`, each canvas pixel the rectangle resolves to is written once.
+    #[inline]
+    pub fn fill_rect(&mut self, start: IVec2, size: UVec2, rgba_u32: u32, options: &DrawOptions) {
+        let canvas_size = self.layout.canvas_size;
+        let columns = axis_cover(start.x as i64..start.x as i64 + size.x as i64, canvas_size.x, self.wrap.x);
+        let mut row = Vec::new();
+        for ys in axis_cover(start.y as i64..start.y as i64 + size.y as i64, canvas_size.y, self.wrap.y) {
+            for y in ys {
+                self.fill_columns(y, columns.clone(), &mut row, rgba_u32, options);
+            }
+        }
+    }
+
+    /// Fill the covered `columns` of canvas row `y`, reusing `row` as the source buffer.
+    #[inline]
+    fn fill_columns(&mut self, y: u32, columns: [Range<u32>; 2], row: &mut Vec<u32>, rgba_u32: u32, options: &DrawOptions) {
+        for xs in columns {
+            row.resize(xs.len(), rgba_u32);
+            self.write_runs(UVec2::new(xs.start, y), row, options);
+        }
+    }
+
+    /// Write one source row to canvas row `y` from column `x`, resolving columns by the X wrap mode.
+    #[inline]
+    fn write_row(&mut self, x: i64, y: u32, src: &[u32], options: &DrawOptions) {
+        for segment in axis_segments(x, src.len() as u32, self.layout.canvas_size.x, self.wrap.x) {
+            let src = &src[segment.src as usize..][..segment.len as usize];
+            match segment.step {
+                SegmentStep::Forward => self.write_runs(UVec2::new(segment.dst, y), src, options),
+                // Mirrored pixels run right to left, so write them reversed from the leftmost column
+                SegmentStep::Reverse => {
+                    let reversed: Vec<u32> = src.iter().rev().copied().collect();
+                    self.write_runs(UVec2::new(segment.dst + 1 - segment.len, y), &reversed, options);
+                }
+                // Clamped pixels all land on the edge column, in source order
+                SegmentStep::Repeat => {
+                    for colour in src {
+                        self.write_run_at(UVec2::new(segment.dst, y), core::slice::from_ref(colour), options);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write pixels along one canvas row from an in-bounds `start`, split into runs at chunk boundaries.
+    ///
+    /// `src.len()` must not cross the end of the canvas row.
+    #[inline]
+    fn write_runs(&mut self, start: UVec2, src: &[u32], options: &DrawOptions) {
+        let mut pos = start;
+        let mut remaining = src;
+        while !remaining.is_empty() {
+            let run = remaining.len().min(self.layout.max_run_len(pos) as usize);
+            let (run_src, rest) = remaining.split_at(run);
+            self.write_run_at(pos, run_src, options);
+            pos.x += run as u32;
+            remaining = rest;
+        }
+    }
+
+    /// Writes a contiguous run on a single scanline into chunk, combining through `options`.
+    ///
+    /// `dst_start` is resolved by the wrap modes, and the run is discarded if it is clipped.
+    /// Runs of more than one pixel should start inside the canvas, and `src.len()` must not cross the end of the
+    /// canvas row, or the end of the chunk row. This should be enforced by using `layout.max_run_len(p)` at call sites.
+    #[inline]
+    pub fn write_run(&mut self, dst_start: IVec2, src: &[u32], options: &DrawOptions) {
+        if let Some(pos) = self.wrap.resolve(dst_start.as_i64vec2(), self.layout.canvas_size) {
+            self.write_run_at(pos, src, options);
+        }
+    }
+
+    /// Writes a contiguous run starting at an in-bounds canvas position (see `write_run`).
+    #[inline]
+    fn write_run_at(&mut self, pos: UVec2, src: &[u32], options: &DrawOptions) {
+        if src.is_empty() {
+            return;
+        }
+
+        let layout = self.layout;
+
+        // Validate that the run is boundary-safe
+        let max_run = layout.max_run_len(pos) as usize;
+        debug_assert!(
+            src.len() <= max_run,
+            "write_run called with a run that crosses a row boundary"
+        );
+
+        // Resolve destination chunk and chunk-local coords
+        let chunk_key = layout.chunk_xy(pos);
+        let local = layout.local_xy(pos);
+
+        // Write into row-major storage (chunk-local or canvas-wide)
+        let (buffer_index, dst_index) = self.cpu.locate(pos);
+        let dst = self.cpu.buffer_mut(buffer_index);
+        debug_assert!(dst_index + src.len() <= dst.len());
+
+        match self.quantiser {
+            None => dst.write(dst_index, src, options),
+            Some(quantiser) => {
+                // Modulate then quantise in batches; the op and mask are applied on write
+                let write_options = options.without_modulation();
+                let mut batch = [0u32; QUANTISE_BATCH];
+                for (i, src_batch) in src.chunks(QUANTISE_BATCH).enumerate() {
+                    let offset = i * QUANTISE_BATCH;
+                    let batch = &mut batch[..src_batch.len()];
+                    for (b, &s) in batch.iter_mut().zip(src_batch) {
+                        *b = options.modulate(s);
+                    }
+                    quantiser.quantise_run(pos.x + offset as u32, pos.y, batch);
+                    dst.write(dst_index + offset, batch, &write_options);
+                }
+            }
+        }
+
+        // Dirty rect: mark the span (width = run, height = 1)
+        self.dirty.mark_rect(&chunk_key, local, UVec2::new(src.len() as u32, 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CanvasChunking, CanvasConfig, WrapMode};
+
+    const MODES: [WrapMode; 4] = [WrapMode::Wrap, WrapMode::Clip, WrapMode::Clamp, WrapMode::Mirror];
+
+    /// A 10x6 canvas of ragged 4x4 chunks, cleared to zero.
+    fn canvas(wrap: CanvasWrap) -> (CanvasConfig, CanvasCpuChunks, CanvasDirtyRects) {
+        let config = CanvasConfig {
+            canvas_size: UVec2::new(10, 6),
+            chunking: CanvasChunking::Size(UVec2::splat(4)),
+            clear_colour: 0,
+            wrap,
+            ..default()
+        };
+        let cpu = CanvasCpuChunks::new(config.layout(), config.pixel_format(), config.storage(), 0);
+        let dirty = CanvasDirtyRects::new(config.num_chunks(), config.chunk_size());
+        (config, cpu, dirty)
+    }
+
+    fn read_all(cpu: &CanvasCpuChunks, size: UVec2) -> Vec<u32> {
+        (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(|pos| {
+                let (buffer_index, index) = cpu.locate(pos);
+                cpu.buffer(buffer_index).get(index)
+            })
+            .collect()
+    }
+
+    /// Expected canvas after writing `src` pixel by pixel at `positions`, resolved by `wrap`.
+    fn expected(wrap: CanvasWrap, size: UVec2, positions: impl Iterator<Item = IVec2>, src: &[u32]) -> Vec<u32> {
+        let mut pixels = vec![0; size.element_product() as usize];
+        for (pos, &colour) in positions.zip(src) {
+            if let Some(pos) = wrap.resolve(pos.as_i64vec2(), size) {
+                pixels[(pos.y * size.x + pos.x) as usize] = colour;
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn rect_resolves_both_edges_per_mode() {
+        for x in MODES {
+            for y in MODES {
+                let wrap = CanvasWrap { x, y };
+                let (config, mut cpu, mut dirty) = canvas(wrap);
+                let (start, size) = (IVec2::new(-3, 4), UVec2::new(15, 4));
+                let src: Vec<u32> = (1..=size.element_product()).collect();
+
+                CanvasWriter::new(&mut cpu, &mut dirty, config.layout(), wrap, None).rect(
+                    start,
+                    size,
+                    &src,
+                    &DrawOptions::default(),
+                );
+
+                let positions = (0..size.y as i32).flat_map(|dy| (0..size.x as i32).map(move |dx| start + IVec2::new(dx, dy)));
+                assert_eq!(
+                    read_all(&cpu, config.canvas_size()),
+                    expected(wrap, config.canvas_size(), positions, &src),
+                    "{wrap:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fill_rect_covers_resolved_pixels() {
+        for x in MODES {
+            for y in MODES {
+                let wrap = CanvasWrap { x, y };
+                let (config, mut cpu, mut dirty) = canvas(wrap);
+                let (start, size) = (IVec2::new(-3, 4), UVec2::new(15, 4));
+
+                CanvasWriter::new(&mut cpu, &mut dirty, config.layout(), wrap, None).fill_rect(
+                    start,
+                    size,
+                    7,
+                    &DrawOptions::default(),
+                );
+
+                let positions = (0..size.y as i32).flat_map(|dy| (0..size.x as i32).map(move |dx| start + IVec2::new(dx, dy)));
+                let src = vec![7; size.element_product() as usize];
+                assert_eq!(
+                    read_all(&cpu, config.canvas_size()),
+                    expected(wrap, config.canvas_size(), positions, &src),
+                    "{wrap:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fill_rect_is_bounded_by_the_canvas() {
+        for mode in MODES {
+            let wrap = CanvasWrap::splat(mode);
+            let (config, mut cpu, mut dirty) = canvas(wrap);
+            CanvasWriter::new(&mut cpu, &mut dirty, config.layout(), wrap, None).fill_rect(
+                IVec2::MIN,
+                UVec2::MAX,
+                7,
+                &DrawOptions::default(),
+            );
+            assert_eq!(read_all(&cpu, config.canvas_size()), vec![7; 60], "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn span_rows_resolve_like_rect_rows() {
+        for mode in MODES {
+            let wrap = CanvasWrap {
+                x: mode,
+                y: WrapMode::Clip,
+            };
+            let (config, mut cpu, mut dirty) = canvas(wrap);
+            let src: Vec<u32> = (1..=14).collect();
+
+            CanvasWriter::new(&mut cpu, &mut dirty, config.layout(), wrap, None).span(
+                IVec2::new(-2, 0),
+                &src,
+                &DrawOptions::default(),
+            );
+
+            // The first row ends with its canvas-width period at X = 0, then later rows span the canvas
+            let positions = (-2..0)
+                .map(|x| IVec2::new(x, 0))
+                .chain((0..10).map(|x| IVec2::new(x, 1)))
+                .chain((0..2).map(|x| IVec2::new(x, 2)));
+            assert_eq!(
+                read_all(&cpu, config.canvas_size()),
+                expected(wrap, config.canvas_size(), positions, &src),
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn wrapped_span_starts_are_equivalent() {
+        let wrap = CanvasWrap::splat(WrapMode::Wrap);
+        let src: Vec<u32> = (1..=25).collect();
+        let draw = |start: IVec2| {
+            let (config, mut cpu, mut dirty) = canvas(wrap);
+            CanvasWriter::new(&mut cpu, &mut dirty, config.layout(), wrap, None).span(start, &src, &DrawOptions::default());
+            read_all(&cpu, config.canvas_size())
+        };
+        assert_eq!(draw(IVec2::new(-2, 0)), draw(IVec2::new(8, 0)));
+        assert_eq!(draw(IVec2::new(-12, 0)), draw(IVec2::new(18, 0)));
+    }
+}